@@ -1,5 +1,6 @@
 use crate::validation::Priority;
 use clap::{Parser, Subcommand, ValueEnum, ValueHint};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "askr")]
@@ -12,6 +13,7 @@ ENVIRONMENT VARIABLES:
   ASKR_NO_COLOR      Disable colored output (same as --no-color)
   ASKR_WIDTH         Set default display width in columns
   ASKR_TIMEOUT       Set default timeout in seconds
+  ASKR_MENU_HEIGHT   Set default number of choices shown at once
 
   Example:
     export ASKR_NO_COLOR=1
@@ -25,6 +27,13 @@ SHELL INTEGRATION EXAMPLES:
   # Pick from git branches
   askr \"Switch to:\" --choices \"$(git branch --format='%(refname:short)')\"
 
+  # Large lists without argv limits: one choice per line from a file, stdin or a command
+  find . -name '*.rs' | askr \"Choose file:\" --choices-file -
+  askr \"Switch to:\" --choices-cmd \"git branch --format='%(refname:short)'\"
+
+  # Grouped choices, at most one backend
+  askr \"Components:\" --choices \"Backend:api,db;Frontend:web,mobile\" --group-max-choices Backend=1
+
   # Git tags with space-delimited output
   askr \"Select tags:\" --choices \"$(git tag)\" --selection-separator \" \" --min-choices 2
 
@@ -53,6 +62,17 @@ pub enum Commands {
         #[arg(value_enum)]
         shell: Shell,
     },
+
+    /// Prompt for several fields described by a form spec (TOML, YAML or JSON)
+    Form {
+        /// Path to the form spec file
+        #[arg(value_hint = ValueHint::FilePath)]
+        spec: PathBuf,
+
+        /// Output format for the collected answers
+        #[arg(long, value_enum, default_value = "json")]
+        output: FormOutputFormat,
+    },
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -81,6 +101,11 @@ pub struct PromptArgs {
     #[arg(long)]
     pub verbose: bool,
 
+    // Rules File
+    /// Load validation rules from a TOML, YAML or JSON file (CLI flags override rules of the same type)
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub rules: Option<PathBuf>,
+
     // Basic Validation
     /// Input cannot be empty
     #[arg(long)]
@@ -123,6 +148,19 @@ pub struct PromptArgs {
     #[arg(long)]
     pub validate_ipv6: bool,
 
+    // External Validation
+    /// Validate with an external command; input is piped to stdin and exit status 0 passes (can be used multiple times)
+    #[arg(long, value_hint = ValueHint::CommandString)]
+    pub validate_cmd: Vec<String>,
+
+    /// Custom error message for command validation (applies to the matching --validate-cmd)
+    #[arg(long)]
+    pub validate_cmd_message: Vec<String>,
+
+    /// Timeout in seconds for command validation (applies to the matching --validate-cmd, default: 10)
+    #[arg(long)]
+    pub validate_cmd_timeout: Vec<u64>,
+
     // Number Validation
     /// Accept only numeric input
     #[arg(long)]
@@ -175,9 +213,17 @@ pub struct PromptArgs {
 
     // Choice Validation
     /// Comma or newline-separated list of valid choices
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["choices_file", "choices_cmd"])]
     pub choices: Option<String>,
 
+    /// Read choices from a file, one per line ("-" for stdin)
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "choices_cmd")]
+    pub choices_file: Option<PathBuf>,
+
+    /// Run a shell command and use its output lines as choices
+    #[arg(long, value_hint = ValueHint::CommandString)]
+    pub choices_cmd: Option<String>,
+
     /// Custom separator for parsing choices (default: auto-detect comma/newline)
     #[arg(long)]
     pub choice_separator: Option<String>,
@@ -198,6 +244,30 @@ pub struct PromptArgs {
     #[arg(long)]
     pub max_choices: Option<usize>,
 
+    /// Output selections in a user-chosen order: pick in order of preference and reorder with Shift+arrows
+    #[arg(long)]
+    pub ranked: bool,
+
+    /// Minimum choices from one group, as GROUP=N (can be used multiple times)
+    #[arg(long, value_name = "GROUP=N")]
+    pub group_min_choices: Vec<String>,
+
+    /// Maximum choices from one group, as GROUP=N (can be used multiple times)
+    #[arg(long, value_name = "GROUP=N")]
+    pub group_max_choices: Vec<String>,
+
+    /// Show a choice but make it unselectable, as VALUE or VALUE=REASON (can be used multiple times)
+    #[arg(long, value_name = "VALUE[=REASON]")]
+    pub disabled_choice: Vec<String>,
+
+    /// Keep a choice always selected (can be used multiple times)
+    #[arg(long, value_name = "VALUE")]
+    pub locked_choice: Vec<String>,
+
+    /// Maximum number of choices shown at once; longer lists scroll (default: fit the terminal)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub menu_height: Option<u16>,
+
     // File System Validation
     /// File must exist
     #[arg(long)]
@@ -261,6 +331,34 @@ pub struct PromptArgs {
     #[arg(long)]
     pub confirm: bool,
 
+    /// Edit several lines of text: Enter starts a new line, CTRL+D or ALT+Enter submits
+    #[arg(long, conflicts_with_all = ["mask", "confirm"])]
+    pub multiline: bool,
+
+    /// Start by editing the answer in $VISUAL/$EDITOR (also available with CTRL+X CTRL+E)
+    #[arg(long, conflicts_with = "mask")]
+    pub editor: bool,
+
+    /// What a single-line prompt does with pasted text that spans several lines
+    #[arg(long, value_enum, value_name = "MODE", default_value = "multiline")]
+    pub paste_newlines: PasteMode,
+
+    /// Type choice answers as text (with Tab completion) instead of picking from a menu
+    #[arg(long)]
+    pub free_text: bool,
+
+    /// Complete input on Tab from the lines this shell command prints; the text before the cursor is piped to its stdin
+    #[arg(long, value_hint = ValueHint::CommandString)]
+    pub complete_cmd: Option<String>,
+
+    /// Remember answers under this name and recall them with ↑/↓ (never used with --mask)
+    #[arg(long, value_name = "KEY")]
+    pub history_key: Option<String>,
+
+    /// Maximum number of answers remembered per history key (default: 500)
+    #[arg(long, value_name = "N", requires = "history_key")]
+    pub history_size: Option<usize>,
+
     // Display Control
     /// Disable colored output
     #[arg(long)]
@@ -282,6 +380,25 @@ pub enum OutputFormat {
     Raw,
 }
 
+/// Handling of line breaks pasted into a single-line prompt. Trailing line
+/// breaks are always dropped.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum PasteMode {
+    /// Continue in the multi-line editor
+    #[default]
+    Multiline,
+    /// Join the lines with spaces
+    Join,
+    /// Refuse the paste
+    Reject,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum FormOutputFormat {
+    Json,
+    Env,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum PriorityArg {
     Critical,
@@ -0,0 +1,58 @@
+use crate::error::{PromptError, Result};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+/// Read the contents of a choices file, or stdin when the path is "-"
+pub fn read_choices_file(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| PromptError::ChoicesSource(format!("stdin: {}", e)))?;
+        return Ok(contents);
+    }
+
+    fs::read_to_string(path)
+        .map_err(|e| PromptError::ChoicesSource(format!("{}: {}", path.display(), e)))
+}
+
+/// Run a shell command and return its standard output
+pub fn run_choices_command(command: &str) -> Result<String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+
+    let output = shell
+        .output()
+        .map_err(|e| PromptError::ChoicesSource(format!("'{}': {}", command, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let status = output
+            .status
+            .code()
+            .map_or_else(|| "a signal".to_string(), |code| format!("status {}", code));
+        return Err(PromptError::ChoicesSource(format!(
+            "'{}': exited with {}{}",
+            command,
+            status,
+            if stderr.trim().is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", stderr.trim())
+            }
+        )));
+    }
+
+    String::from_utf8(output.stdout).map_err(|_| {
+        PromptError::ChoicesSource(format!("'{}': output is not valid UTF-8", command))
+    })
+}
@@ -1,7 +1,11 @@
-use super::args::{OutputFormat, PromptArgs};
+use super::args::{OutputFormat, PasteMode, PromptArgs};
+use super::choices::{read_choices_file, run_choices_command};
+use super::rules::{check_rule, load_rules_file};
 use crate::error::{PromptError, Result};
-use crate::validation::{ValidationRuleConfig, ValidatorType};
+use crate::input::history::DEFAULT_HISTORY_SIZE;
+use crate::validation::{ChoiceOption, ValidationRuleConfig, ValidatorType};
 use std::collections::HashMap;
+use std::path::Path;
 use std::time::Duration;
 
 /// Main configuration for the prompt tool
@@ -20,6 +24,7 @@ pub struct PromptConfig {
 pub struct UiConfig {
     pub no_color: bool,
     pub width: Option<u16>,
+    pub menu_height: Option<u16>,
     pub help_text: Option<String>,
 }
 
@@ -30,11 +35,25 @@ pub struct InteractionConfig {
     pub default_value: Option<String>,
     pub mask_input: bool,
     pub require_confirmation: bool,
+    pub allow_back: bool,
+    pub multiline: bool,
+    pub open_editor: bool,
+    pub paste_newlines: PasteMode,
+    pub free_text: bool,
+    pub complete_cmd: Option<String>,
+    pub history_key: Option<String>,
+    pub history_size: usize,
+    pub stdin_choices: bool, // --choices-file - read stdin, so it holds no answer
 }
 
 impl PromptConfig {
     pub fn from_args(args: PromptArgs) -> Result<Self> {
-        let validation_rules = Self::build_validation_rules(&args)?;
+        let file_rules = match &args.rules {
+            Some(path) => load_rules_file(path)?,
+            None => Vec::new(),
+        };
+        let validation_rules =
+            Self::layer_validation_rules(file_rules, Self::build_validation_rules(&args)?);
 
         Ok(Self {
             prompt_text: args.prompt_text,
@@ -45,6 +64,7 @@ impl PromptConfig {
             ui_config: UiConfig {
                 no_color: Self::resolve_no_color(args.no_color),
                 width: Self::resolve_width(args.width),
+                menu_height: Self::resolve_menu_height(args.menu_height),
                 help_text: args.help_text,
             },
             interaction_config: InteractionConfig {
@@ -53,6 +73,15 @@ impl PromptConfig {
                 default_value: args.default,
                 mask_input: args.mask,
                 require_confirmation: args.confirm,
+                allow_back: false,
+                multiline: args.multiline,
+                open_editor: args.editor,
+                paste_newlines: args.paste_newlines,
+                free_text: args.free_text,
+                complete_cmd: args.complete_cmd,
+                history_key: Self::resolve_history_key(args.history_key)?,
+                history_size: args.history_size.unwrap_or(DEFAULT_HISTORY_SIZE),
+                stdin_choices: args.choices_file.as_deref() == Some(Path::new("-")),
             },
         })
     }
@@ -75,6 +104,26 @@ impl PromptConfig {
         })
     }
 
+    /// Resolve choice menu height from CLI args or environment variable
+    fn resolve_menu_height(cli_menu_height: Option<u16>) -> Option<u16> {
+        cli_menu_height.or_else(|| {
+            std::env::var("ASKR_MENU_HEIGHT")
+                .ok()
+                .and_then(|s| s.parse::<u16>().ok())
+                .filter(|&height| height > 0)
+        })
+    }
+
+    /// Reject history keys that would not name a history file
+    fn resolve_history_key(history_key: Option<String>) -> Result<Option<String>> {
+        match history_key {
+            Some(key) if key.trim().is_empty() => Err(PromptError::InvalidArguments(
+                "--history-key cannot be empty".to_string(),
+            )),
+            key => Ok(key),
+        }
+    }
+
     /// Resolve timeout setting from CLI args or environment variable
     fn resolve_timeout(cli_timeout: Option<u64>) -> Option<Duration> {
         cli_timeout.map(Duration::from_secs).or_else(|| {
@@ -174,6 +223,20 @@ impl PromptConfig {
             });
         }
 
+        // External command validations (can have multiple)
+        for (i, command) in args.validate_cmd.iter().enumerate() {
+            let mut parameters = HashMap::new();
+            if let Some(timeout) = args.validate_cmd_timeout.get(i) {
+                parameters.insert("timeout".to_string(), timeout.to_string());
+            }
+            rules.push(ValidationRuleConfig {
+                validator_type: ValidatorType::Command(command.clone()),
+                priority: None,
+                custom_message: args.validate_cmd_message.get(i).cloned(),
+                parameters,
+            });
+        }
+
         // Number validations
         if args.integer {
             rules.push(ValidationRuleConfig {
@@ -259,17 +322,40 @@ impl PromptConfig {
             });
         }
 
-        // Choice validation - support custom separators
+        // Choice validation - support custom separators. Files and command
+        // output hold one choice per line unless a separator is given.
         let choices_opt = if let Some(choices_str) = &args.choices {
             Some(Self::parse_choices(
                 choices_str,
                 args.choice_separator.as_deref(),
-            ))
+            )?)
+        } else if let Some(path) = &args.choices_file {
+            if args.quiet && path == Path::new("-") {
+                return Err(PromptError::InvalidArguments(
+                    "--choices-file - cannot be used with --quiet, which reads the answer from stdin"
+                        .to_string(),
+                ));
+            }
+            let contents = read_choices_file(path)?;
+            Some(Self::parse_source_choices(
+                &contents,
+                &path.display().to_string(),
+                args.choice_separator.as_deref(),
+            )?)
+        } else if let Some(command) = &args.choices_cmd {
+            let output = run_choices_command(command)?;
+            Some(Self::parse_source_choices(
+                &output,
+                &format!("'{}'", command),
+                args.choice_separator.as_deref(),
+            )?)
         } else {
             None
         };
 
-        if let Some(choices) = choices_opt {
+        if let Some(mut choices) = choices_opt {
+            Self::apply_choice_states(&mut choices, args)?;
+
             let mut parameters = HashMap::new();
             parameters.insert(
                 "case_sensitive".to_string(),
@@ -293,6 +379,26 @@ impl PromptConfig {
 
             parameters.insert("min_choices".to_string(), min_choices.to_string());
             parameters.insert("max_choices".to_string(), max_choices.to_string());
+            if args.ranked {
+                parameters.insert("ranked".to_string(), "true".to_string());
+            }
+            for (flag, limit, specs) in [
+                (
+                    "--group-min-choices",
+                    "min_choices",
+                    &args.group_min_choices,
+                ),
+                (
+                    "--group-max-choices",
+                    "max_choices",
+                    &args.group_max_choices,
+                ),
+            ] {
+                for spec in specs {
+                    let (group, count) = Self::parse_group_limit(flag, spec)?;
+                    parameters.insert(format!("{}:{}", limit, group), count.to_string());
+                }
+            }
             parameters.insert(
                 "selection_separator".to_string(),
                 args.selection_separator
@@ -301,12 +407,14 @@ impl PromptConfig {
                     .to_string(),
             );
 
-            rules.push(ValidationRuleConfig {
+            let rule = ValidationRuleConfig {
                 validator_type: ValidatorType::Choices(choices),
                 priority: args.format_priority.clone().map(Into::into),
                 custom_message: None,
                 parameters,
-            });
+            };
+            check_rule(&rule).map_err(PromptError::InvalidArguments)?;
+            rules.push(rule);
         }
 
         // File system validations
@@ -367,6 +475,31 @@ impl PromptConfig {
         Ok(rules)
     }
 
+    /// Layer CLI-derived rules on top of rules loaded from a file. A CLI rule
+    /// replaces file rules of the same type, except for patterns and commands,
+    /// which accumulate.
+    fn layer_validation_rules(
+        file_rules: Vec<ValidationRuleConfig>,
+        cli_rules: Vec<ValidationRuleConfig>,
+    ) -> Vec<ValidationRuleConfig> {
+        let overridden: Vec<_> = cli_rules
+            .iter()
+            .filter(|rule| {
+                !matches!(
+                    rule.validator_type,
+                    ValidatorType::Pattern(_) | ValidatorType::Command(_)
+                )
+            })
+            .map(|rule| std::mem::discriminant(&rule.validator_type))
+            .collect();
+
+        file_rules
+            .into_iter()
+            .filter(|rule| !overridden.contains(&std::mem::discriminant(&rule.validator_type)))
+            .chain(cli_rules)
+            .collect()
+    }
+
     fn parse_range(range_str: &str) -> Result<(f64, f64)> {
         let parts: Vec<&str> = range_str.split('-').collect();
         if parts.len() != 2 {
@@ -394,35 +527,275 @@ impl PromptConfig {
         Ok((min, max))
     }
 
-    /// Parse choices from string, supporting custom separators
-    fn parse_choices(choices_str: &str, custom_separator: Option<&str>) -> Vec<String> {
-        match custom_separator {
-            Some(separator) => {
-                // Use custom separator
-                choices_str
-                    .split(separator)
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect()
+    /// Mark the choices named by `--disabled-choice` and `--locked-choice`
+    fn apply_choice_states(choices: &mut [ChoiceOption], args: &PromptArgs) -> Result<()> {
+        let find = |flag: &str, value: &str| {
+            choices
+                .iter()
+                .position(|choice| choice.value == value)
+                .ok_or_else(|| {
+                    PromptError::InvalidArguments(format!(
+                        "{} refers to unknown choice '{}'",
+                        flag, value
+                    ))
+                })
+        };
+
+        let mut disabled = Vec::new();
+        for spec in &args.disabled_choice {
+            let (value, reason) = spec.split_once('=').unwrap_or((spec, ""));
+            disabled.push((find("--disabled-choice", value.trim())?, reason.trim()));
+        }
+        let mut locked = Vec::new();
+        for value in &args.locked_choice {
+            locked.push(find("--locked-choice", value.trim())?);
+        }
+
+        for (i, reason) in disabled {
+            choices[i].disabled = Some(reason.to_string());
+        }
+        for i in locked {
+            choices[i].locked = true;
+        }
+        Ok(())
+    }
+
+    /// Parse a `GROUP=N` per-group choice limit
+    fn parse_group_limit<'a>(flag: &str, spec: &'a str) -> Result<(&'a str, usize)> {
+        spec.rsplit_once('=')
+            .and_then(|(group, count)| {
+                let group = group.trim();
+                let count = count.trim().parse::<usize>().ok()?;
+                (!group.is_empty()).then_some((group, count))
+            })
+            .ok_or_else(|| {
+                PromptError::InvalidArguments(format!("{} expects GROUP=N, got '{}'", flag, spec))
+            })
+    }
+
+    /// Parse choices read from a file or command, which must yield at least
+    /// one. Each line (or separated entry) is a literal value, so file names
+    /// containing `=` or branch names like `[draft] fix` are kept as they are.
+    fn parse_source_choices(
+        contents: &str,
+        source: &str,
+        custom_separator: Option<&str>,
+    ) -> Result<Vec<ChoiceOption>> {
+        let choices: Vec<ChoiceOption> = match custom_separator {
+            Some(separator) => contents.split(separator).collect::<Vec<_>>(),
+            None => contents.lines().collect(),
+        }
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ChoiceOption::new)
+        .collect();
+        if choices.is_empty() {
+            return Err(PromptError::ChoicesSource(format!(
+                "{}: no choices found",
+                source
+            )));
+        }
+        Ok(choices)
+    }
+
+    /// Parse choices from string, supporting custom separators. Each entry is a
+    /// value or `value=Label`, with `\=` for a literal `=`; a JSON array may
+    /// also be given, whose items are values or `{"value", "label",
+    /// "description"}` objects.
+    fn parse_choices(
+        choices_str: &str,
+        custom_separator: Option<&str>,
+    ) -> Result<Vec<ChoiceOption>> {
+        // Only text that is a JSON array as a whole is read as JSON, so plain
+        // choices such as `[wip],[done]` keep working
+        if let Ok(json @ serde_json::Value::Array(_)) = serde_json::from_str(choices_str) {
+            return serde_json::from_value(json).map_err(|e| {
+                PromptError::InvalidArguments(format!("Invalid JSON choices: {}", e))
+            });
+        }
+
+        if custom_separator.is_none() {
+            if let Some(groups) = Self::parse_grouped_choices(choices_str) {
+                return Ok(groups);
             }
-            None => {
-                // Auto-detect: prioritize newlines over commas
-                if choices_str.contains('\n') {
-                    // Parse as newline-separated
-                    choices_str
-                        .lines()
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect()
-                } else {
-                    // Parse as comma-separated
-                    choices_str
-                        .split(',')
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect()
-                }
+        }
+
+        let entries: Vec<&str> = match custom_separator {
+            // Use custom separator
+            Some(separator) => choices_str.split(separator).collect(),
+            // Auto-detect: prioritize newlines over commas
+            None if choices_str.contains('\n') => choices_str.lines().collect(),
+            None => choices_str.split(',').collect(),
+        };
+
+        Ok(entries
+            .into_iter()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Self::parse_choice_entry)
+            .collect())
+    }
+
+    /// Parse the inline grouped syntax `Backend:api,db;Frontend:web`. Only
+    /// single-line input where every `;`-separated segment starts with a
+    /// `Group:` header is treated as grouped, so ordinary choices containing
+    /// colons (e.g. URLs) are left alone; `\:` keeps a colon from starting a
+    /// group.
+    fn parse_grouped_choices(choices_str: &str) -> Option<Vec<ChoiceOption>> {
+        if choices_str.contains('\n') || !choices_str.contains(';') {
+            return None;
+        }
+
+        let mut choices = Vec::new();
+        for segment in choices_str.split(';').filter(|s| !s.trim().is_empty()) {
+            let (group, entries) = split_unescaped(segment, ':')?;
+            let group = group.trim();
+            if group.is_empty() || group.contains(',') || entries.starts_with("//") {
+                return None;
             }
+            choices.extend(
+                entries
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|entry| Self::parse_choice_entry(entry).with_group(group)),
+            );
         }
+        Some(choices)
+    }
+
+    /// A `value=Label` entry, or a plain value
+    fn parse_choice_entry(entry: &str) -> ChoiceOption {
+        match split_unescaped(entry, '=') {
+            Some((value, label)) if !value.trim().is_empty() && !label.trim().is_empty() => {
+                ChoiceOption::new(unescape_choice(value.trim()))
+                    .with_label(unescape_choice(label.trim()))
+            }
+            _ => ChoiceOption::new(unescape_choice(entry)),
+        }
+    }
+}
+
+/// Split at the first `delimiter` that is not escaped with a backslash
+fn split_unescaped(text: &str, delimiter: char) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            return Some((&text[..i], &text[i + c.len_utf8()..]));
+        }
+    }
+    None
+}
+
+/// Drop the backslashes from escaped choice syntax
+fn unescape_choice(text: &str) -> String {
+    text.replace("\\=", "=").replace("\\:", ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_choices_with_labels() {
+        let choices = PromptConfig::parse_choices("dev, prod=Production ,=odd", None).unwrap();
+        assert_eq!(
+            choices,
+            vec![
+                ChoiceOption::new("dev"),
+                ChoiceOption::new("prod").with_label("Production"),
+                ChoiceOption::new("=odd"),
+            ]
+        );
+
+        let choices = PromptConfig::parse_choices("a=Alpha\nb\n", None).unwrap();
+        assert_eq!(choices[0].display_label(), "Alpha");
+        assert_eq!(choices[1].display_label(), "b");
+
+        // An escaped `=` is part of the value
+        let choices = PromptConfig::parse_choices(r"x\=1, y\=2=Second", None).unwrap();
+        assert_eq!(
+            choices,
+            vec![
+                ChoiceOption::new("x=1"),
+                ChoiceOption::new("y=2").with_label("Second"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_choices_json() {
+        let choices = PromptConfig::parse_choices(
+            r#"["dev", {"value": "prod", "label": "Production", "description": "us-east-1"}]"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            choices,
+            vec![
+                ChoiceOption::new("dev"),
+                ChoiceOption {
+                    description: Some("us-east-1".to_string()),
+                    ..ChoiceOption::new("prod").with_label("Production")
+                },
+            ]
+        );
+
+        let err = PromptConfig::parse_choices(r#"[{"label": "No value"}]"#, None).unwrap_err();
+        assert!(matches!(err, PromptError::InvalidArguments(_)));
+
+        // Text that isn't JSON as a whole is a plain list
+        let choices = PromptConfig::parse_choices("[wip],[done]", None).unwrap();
+        assert_eq!(
+            choices,
+            vec![ChoiceOption::new("[wip]"), ChoiceOption::new("[done]")]
+        );
+    }
+
+    #[test]
+    fn test_parse_source_choices_literally() {
+        let choices =
+            PromptConfig::parse_source_choices("report=final.txt\n[draft] fix\n\n", "-", None)
+                .unwrap();
+        assert_eq!(
+            choices,
+            vec![
+                ChoiceOption::new("report=final.txt"),
+                ChoiceOption::new("[draft] fix"),
+            ]
+        );
+
+        let err = PromptConfig::parse_source_choices("\n", "list.txt", None).unwrap_err();
+        assert!(matches!(err, PromptError::ChoicesSource(_)));
+    }
+
+    #[test]
+    fn test_parse_grouped_choices() {
+        let choices =
+            PromptConfig::parse_choices("Backend: api, db=Database; Frontend: web", None).unwrap();
+        assert_eq!(
+            choices,
+            vec![
+                ChoiceOption::new("api").with_group("Backend"),
+                ChoiceOption::new("db")
+                    .with_label("Database")
+                    .with_group("Backend"),
+                ChoiceOption::new("web").with_group("Frontend"),
+            ]
+        );
+
+        // Colons and semicolons alone don't make a list grouped
+        let choices = PromptConfig::parse_choices("http://a;http://b", None).unwrap();
+        assert_eq!(choices.len(), 1);
+        assert!(choices.iter().all(|choice| choice.group.is_none()));
+
+        // An escaped colon keeps `k:v;k2:v2` a plain value
+        let choices = PromptConfig::parse_choices(r"k\:v;k2\:v2", None).unwrap();
+        assert_eq!(choices, vec![ChoiceOption::new("k:v;k2:v2")]);
     }
 }
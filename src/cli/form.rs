@@ -0,0 +1,706 @@
+use super::config::PromptConfig;
+use super::rules::{check_rule, deserialize_document, RulesFormat};
+use crate::error::{PromptError, Result};
+use crate::output::form::env_name;
+use crate::validation::{ValidationRuleConfig, ValidatorType};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// A multi-field form loaded from a spec file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FormSpec {
+    pub fields: Vec<FormField>,
+}
+
+/// A single named field in a form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FormField {
+    pub name: String,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Rules are kept unparsed until earlier answers are substituted into them
+    #[serde(default)]
+    pub rules: Vec<Value>,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub help: Option<String>,
+    #[serde(default)]
+    pub mask: bool,
+    #[serde(default)]
+    pub multiline: bool,
+    #[serde(default)]
+    pub when: Option<FieldCondition>,
+    #[serde(default)]
+    pub required_when: Option<FieldCondition>,
+}
+
+/// Condition on an earlier answer that decides whether a field is asked
+/// (`when`) or must be answered (`required_when`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCondition {
+    pub field: String,
+    #[serde(default)]
+    pub equals: Option<String>,
+    #[serde(default, rename = "in")]
+    pub one_of: Option<Vec<String>>,
+    #[serde(default)]
+    pub matches: Option<String>,
+}
+
+impl FieldCondition {
+    /// Check the referenced answer; multi-select answers match if any selection does
+    fn is_met(&self, answer: &str, separator: &str) -> bool {
+        if let Some(expected) = &self.equals {
+            answer == expected
+        } else if let Some(allowed) = &self.one_of {
+            answer
+                .split(separator)
+                .map(str::trim)
+                .any(|selection| allowed.iter().any(|choice| choice == selection))
+        } else if let Some(pattern) = &self.matches {
+            Regex::new(pattern).is_ok_and(|regex| regex.is_match(answer))
+        } else {
+            false
+        }
+    }
+
+    fn check(&self) -> std::result::Result<(), String> {
+        let operators = [
+            self.equals.is_some(),
+            self.one_of.is_some(),
+            self.matches.is_some(),
+        ];
+        if operators.iter().filter(|&&set| set).count() != 1 {
+            return Err("condition needs exactly one of 'equals', 'in' or 'matches'".to_string());
+        }
+        if let Some(pattern) = &self.matches {
+            Regex::new(pattern).map_err(|e| format!("invalid condition pattern: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl FormField {
+    pub fn prompt_text(&self) -> String {
+        self.prompt
+            .clone()
+            .unwrap_or_else(|| format!("{}:", self.name))
+    }
+
+    /// Separator used to join multiple selections from this field's choice rule
+    fn selection_separator(&self) -> &str {
+        self.rules
+            .iter()
+            .filter(|rule| rule.get("type").and_then(Value::as_str) == Some("choices"))
+            .find_map(|rule| rule.get("parameters")?.get("selection_separator")?.as_str())
+            .unwrap_or(",")
+    }
+}
+
+impl FormSpec {
+    /// Whether the field at `index` should be asked given the answers so far
+    pub fn is_active(&self, index: usize, answers: &[Option<String>]) -> bool {
+        match &self.fields[index].when {
+            Some(condition) => self.condition_met(index, condition, answers),
+            None => true,
+        }
+    }
+
+    /// Whether an earlier, asked field's answer meets a condition of the
+    /// field at `index`
+    fn condition_met(
+        &self,
+        index: usize,
+        condition: &FieldCondition,
+        answers: &[Option<String>],
+    ) -> bool {
+        self.fields[..index]
+            .iter()
+            .position(|field| field.name == condition.field)
+            .filter(|&i| self.is_active(i, answers))
+            .and_then(|i| {
+                let answer = answers[i].as_deref()?;
+                Some(condition.is_met(answer, self.fields[i].selection_separator()))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Build the prompt configuration for the field at `index` on top of the
+    /// global options, substituting earlier answers for `{field}` placeholders
+    pub fn prompt_config(
+        &self,
+        index: usize,
+        answers: &[Option<String>],
+        base: &PromptConfig,
+    ) -> PromptConfig {
+        let field = &self.fields[index];
+        let values = self.answer_values(index, answers);
+        let substitute = |text: &str| substitute_answers(text, &values);
+
+        let mut rules = resolve_rules(&field.rules, &values);
+        let required = field
+            .required_when
+            .as_ref()
+            .is_some_and(|condition| self.condition_met(index, condition, answers));
+        if required
+            && !rules
+                .iter()
+                .any(|rule| matches!(rule.validator_type, ValidatorType::Required))
+        {
+            rules.insert(
+                0,
+                ValidationRuleConfig {
+                    validator_type: ValidatorType::Required,
+                    priority: None,
+                    custom_message: None,
+                    parameters: HashMap::new(),
+                },
+            );
+        }
+
+        let mut config = base.clone();
+        config.prompt_text = Some(substitute(&field.prompt_text()));
+        config.validation_rules = rules;
+        config.ui_config.help_text = field.help.as_deref().map(substitute);
+        config.interaction_config.default_value = field.default.as_deref().map(substitute);
+        // Fields add to the global options; masked input is never multi-line
+        let interaction = &mut config.interaction_config;
+        interaction.mask_input |= field.mask;
+        interaction.multiline =
+            (interaction.multiline || field.multiline) && !interaction.mask_input;
+        config
+    }
+
+    /// Answers to the fields that were asked, in field order
+    pub fn collected_answers(&self, answers: &[Option<String>]) -> Vec<(String, String)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.is_active(i, answers))
+            .filter_map(|(i, field)| Some((field.name.clone(), answers[i].clone()?)))
+            .collect()
+    }
+
+    /// Values of the fields before `index`; skipped fields substitute as empty
+    fn answer_values<'a>(
+        &'a self,
+        index: usize,
+        answers: &'a [Option<String>],
+    ) -> HashMap<&'a str, &'a str> {
+        self.fields[..index]
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let value = if self.is_active(i, answers) {
+                    answers[i].as_deref().unwrap_or_default()
+                } else {
+                    ""
+                };
+                (field.name.as_str(), value)
+            })
+            .collect()
+    }
+}
+
+/// Replace `{field}` placeholders naming known fields; other braces (such as
+/// regex quantifiers) are left alone
+fn substitute_answers(text: &str, values: &HashMap<&str, &str>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| Some((end, values.get(&after[..end])?)))
+        {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Substitute answers into a raw rule value. A string that is only a
+/// placeholder becomes a number when the answer is numeric, so that e.g.
+/// `value = [1, "{max_port}"]` works for ranges.
+fn substitute_rule(rule: &Value, values: &HashMap<&str, &str>) -> Value {
+    match rule {
+        Value::String(text) => {
+            let substituted = substitute_answers(text, values);
+            let whole_placeholder = text.starts_with('{')
+                && text.ends_with('}')
+                && values.contains_key(&text[1..text.len() - 1]);
+            if whole_placeholder {
+                if let Ok(number) = substituted.parse::<i64>() {
+                    return Value::from(number);
+                }
+                if let Some(number) = substituted
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                {
+                    return Value::Number(number);
+                }
+            }
+            Value::String(substituted)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_rule(item, values))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute_rule(item, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Substitute answers into a field's rule. Answers put into the regex of a
+/// `pattern` rule are escaped, so they match literally.
+fn substitute_field_rule(rule: &Value, values: &HashMap<&str, &str>) -> Value {
+    let mut substituted = substitute_rule(rule, values);
+    if rule.get("type").and_then(Value::as_str) == Some("pattern") {
+        if let (Some(Value::String(pattern)), Some(value)) =
+            (rule.get("value"), substituted.get_mut("value"))
+        {
+            let escaped: HashMap<&str, String> = values
+                .iter()
+                .map(|(&name, answer)| (name, regex::escape(answer)))
+                .collect();
+            let escaped = escaped
+                .iter()
+                .map(|(&name, answer)| (name, answer.as_str()))
+                .collect();
+            *value = Value::String(substitute_answers(pattern, &escaped));
+        }
+    }
+    substituted
+}
+
+/// Parse the rules of a field once earlier answers have been substituted.
+/// A rule that the answers make unusable, such as a range bound answered
+/// with text, becomes one that rejects input and says why.
+fn resolve_rules(rules: &[Value], values: &HashMap<&str, &str>) -> Vec<ValidationRuleConfig> {
+    let names = values.keys().copied().collect();
+    rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let resolved = ValidationRuleConfig::deserialize(substitute_field_rule(rule, values))
+                .map_err(|e| e.to_string())
+                .and_then(|resolved| check_rule(&resolved).map(|()| resolved));
+            resolved.unwrap_or_else(|message| {
+                let mut referenced = HashSet::new();
+                referenced_fields(rule, &names, &mut referenced);
+                let mut referenced: Vec<_> = referenced
+                    .into_iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect();
+                referenced.sort();
+                ValidationRuleConfig {
+                    validator_type: ValidatorType::Unresolved(format!(
+                        "Rule {} can't use the answer to {}: {}",
+                        i + 1,
+                        referenced.join(", "),
+                        message
+                    )),
+                    priority: None,
+                    custom_message: None,
+                    parameters: HashMap::new(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Names of fields referenced by `{field}` placeholders anywhere in a value
+fn referenced_fields<'a>(value: &Value, names: &HashSet<&'a str>, found: &mut HashSet<&'a str>) {
+    match value {
+        Value::String(text) => {
+            for (start, _) in text.match_indices('{') {
+                if let Some(end) = text[start + 1..].find('}') {
+                    if let Some(name) = names.get(&text[start + 1..start + 1 + end]) {
+                        found.insert(name);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| referenced_fields(v, names, found)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|v| referenced_fields(v, names, found)),
+        _ => {}
+    }
+}
+
+/// Load a form spec from a TOML, YAML or JSON file
+pub fn load_form_spec(path: &Path) -> Result<FormSpec> {
+    let format = RulesFormat::from_path(path).ok_or_else(|| {
+        PromptError::FormSpec(format!(
+            "{}: unsupported file type (expected .toml, .yaml, .yml or .json)",
+            path.display()
+        ))
+    })?;
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| PromptError::FormSpec(format!("{}: {}", path.display(), e)))?;
+
+    parse_form_spec(&contents, format)
+        .map_err(|message| PromptError::FormSpec(format!("{}: {}", path.display(), message)))
+}
+
+/// Parse a form spec from file contents; errors include the parser's line information
+pub fn parse_form_spec(
+    contents: &str,
+    format: RulesFormat,
+) -> std::result::Result<FormSpec, String> {
+    let spec: FormSpec = deserialize_document(contents, format)?;
+
+    if spec.fields.is_empty() {
+        return Err("form must define at least one field".to_string());
+    }
+
+    let all_names: HashSet<&str> = spec.fields.iter().map(|f| f.name.as_str()).collect();
+    let mut earlier = HashSet::new();
+    let mut env_names: HashMap<String, &str> = HashMap::new();
+    for field in &spec.fields {
+        if field.name.trim().is_empty() {
+            return Err("field names cannot be empty".to_string());
+        }
+        if earlier.contains(field.name.as_str()) {
+            return Err(format!("duplicate field name '{}'", field.name));
+        }
+        // `--output env` would write both to one variable
+        if let Some(other) = env_names.insert(env_name(&field.name), &field.name) {
+            return Err(format!(
+                "fields '{}' and '{}' would both be written as {}",
+                other,
+                field.name,
+                env_name(&field.name)
+            ));
+        }
+
+        for condition in field.when.iter().chain(&field.required_when) {
+            if !earlier.contains(condition.field.as_str()) {
+                return Err(format!(
+                    "field '{}' condition refers to '{}', which is not an earlier field",
+                    field.name, condition.field
+                ));
+            }
+            condition
+                .check()
+                .map_err(|message| format!("field '{}' {}", field.name, message))?;
+        }
+
+        for (i, rule) in field.rules.iter().enumerate() {
+            let mut referenced = HashSet::new();
+            referenced_fields(rule, &all_names, &mut referenced);
+            if let Some(later) = referenced.iter().find(|name| !earlier.contains(*name)) {
+                return Err(format!(
+                    "field '{}' rule {} refers to '{}', which is not an earlier field",
+                    field.name,
+                    i + 1,
+                    later
+                ));
+            }
+
+            // Rules that depend on earlier answers can only be fully checked
+            // once those are known; until then zero stands in for each answer
+            let placeholder_values = referenced.iter().map(|&name| (name, "0")).collect();
+            let resolved =
+                ValidationRuleConfig::deserialize(substitute_field_rule(rule, &placeholder_values))
+                    .map_err(|e| e.to_string())
+                    .and_then(|rule| match referenced.is_empty() {
+                        true => check_rule(&rule),
+                        false => Ok(()),
+                    });
+            resolved
+                .map_err(|message| format!("field '{}' rule {}: {}", field.name, i + 1, message))?;
+        }
+
+        earlier.insert(field.name.as_str());
+    }
+
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PromptArgs;
+    use crate::validation::ValidatorType;
+    use clap::Parser;
+
+    fn base_config() -> PromptConfig {
+        PromptConfig::from_args(PromptArgs::parse_from(["askr"])).unwrap()
+    }
+
+    #[test]
+    fn test_parse_form_spec() {
+        let spec = parse_form_spec(
+            r#"
+[[fields]]
+name = "username"
+prompt = "Username:"
+help = "Lowercase letters only"
+rules = [{ type = "required" }, { type = "min_length", value = 3 }]
+
+[[fields]]
+name = "environment"
+default = "dev"
+rules = [{ type = "choices", value = ["dev", "prod"] }]
+"#,
+            RulesFormat::Toml,
+        )
+        .unwrap();
+
+        assert_eq!(spec.fields.len(), 2);
+        assert_eq!(spec.fields[0].prompt_text(), "Username:");
+        assert_eq!(spec.fields[0].rules.len(), 2);
+        assert_eq!(spec.fields[1].prompt_text(), "environment:");
+        assert_eq!(spec.fields[1].default.as_deref(), Some("dev"));
+
+        let answers = vec![Some("alice".to_string()), None];
+        let config = spec.prompt_config(1, &answers, &base_config());
+        assert!(matches!(
+            config.validation_rules[0].validator_type,
+            ValidatorType::Choices(_)
+        ));
+    }
+
+    #[test]
+    fn test_form_spec_errors() {
+        let err = parse_form_spec("fields: []\n", RulesFormat::Yaml).unwrap_err();
+        assert!(err.contains("at least one field"));
+
+        let err = parse_form_spec(
+            "fields:\n  - name: host\n  - name: host\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.contains("duplicate field name 'host'"));
+
+        let err = parse_form_spec(
+            "fields:\n  - name: db-host\n  - name: db_host\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("fields 'db-host' and 'db_host' would both be written as DB_HOST"),
+            "{}",
+            err
+        );
+
+        let err = parse_form_spec(
+            r#"{"fields": [{"name": "port", "rules": [{"type": "range", "value": [10, 1]}]}]}"#,
+            RulesFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("field 'port' rule 1:"));
+
+        let err = parse_form_spec(
+            "fields:\n  - name: cert\n    when: { field: protocol, equals: https }\n  - name: protocol\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.contains("not an earlier field"));
+
+        let err = parse_form_spec(
+            "fields:\n  - name: a\n  - name: b\n    when: { field: a, equals: x, matches: y }\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.contains("exactly one of"));
+    }
+
+    #[test]
+    fn test_conditional_fields() {
+        let spec = parse_form_spec(
+            r#"
+fields:
+  - name: protocol
+    rules: [{ type: choices, value: [http, https] }]
+  - name: cert
+    when: { field: protocol, equals: https }
+  - name: features
+  - name: cache_size
+    when: { field: features, in: [cache] }
+  - name: host
+    when: { field: protocol, matches: "^http" }
+  - name: key
+    when: { field: cert, matches: "." }
+"#,
+            RulesFormat::Yaml,
+        )
+        .unwrap();
+
+        let answers = |values: [&str; 2]| {
+            vec![
+                Some(values[0].to_string()),
+                Some("cert.pem".to_string()),
+                Some(values[1].to_string()),
+                None,
+                None,
+                None,
+            ]
+        };
+
+        let plain = answers(["http", "auth,logs"]);
+        assert!(!spec.is_active(1, &plain));
+        assert!(!spec.is_active(3, &plain));
+        assert!(spec.is_active(4, &plain));
+        // Fields depending on a skipped field are skipped too
+        assert!(!spec.is_active(5, &plain));
+
+        let secure = answers(["https", "auth,cache"]);
+        assert!(spec.is_active(1, &secure));
+        assert!(spec.is_active(3, &secure));
+        assert!(spec.is_active(5, &secure));
+
+        let collected = spec.collected_answers(&plain);
+        assert_eq!(
+            collected
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["protocol", "features"]
+        );
+    }
+
+    #[test]
+    fn test_fields_add_to_global_options() {
+        let spec = parse_form_spec(
+            "fields:\n  - name: notes\n  - name: token\n    mask: true\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap();
+        let base =
+            PromptConfig::from_args(PromptArgs::parse_from(["askr", "--multiline"])).unwrap();
+        let answers = vec![None, None];
+
+        let notes = spec.prompt_config(0, &answers, &base).interaction_config;
+        assert!(notes.multiline && !notes.mask_input);
+        let token = spec.prompt_config(1, &answers, &base).interaction_config;
+        assert!(token.mask_input && !token.multiline);
+    }
+
+    #[test]
+    fn test_required_when() {
+        let spec = parse_form_spec(
+            r#"
+[[fields]]
+name = "protocol"
+
+[[fields]]
+name = "cert"
+required_when = { field = "protocol", equals = "https" }
+rules = [{ type = "file_exists" }]
+"#,
+            RulesFormat::Toml,
+        )
+        .unwrap();
+
+        let rules = |protocol: &str| {
+            let answers = vec![Some(protocol.to_string()), None];
+            spec.prompt_config(1, &answers, &base_config())
+                .validation_rules
+        };
+        assert!(matches!(
+            rules("https")[0].validator_type,
+            ValidatorType::Required
+        ));
+        assert_eq!(rules("https").len(), 2);
+        assert_eq!(rules("http").len(), 1);
+        // The field is asked either way
+        assert!(spec.is_active(1, &[Some("http".to_string()), None]));
+    }
+
+    #[test]
+    fn test_rules_reference_earlier_answers() {
+        let spec = parse_form_spec(
+            r#"
+[[fields]]
+name = "max_port"
+
+[[fields]]
+name = "port"
+prompt = "Port (1-{max_port}):"
+default = "{max_port}"
+rules = [
+    { type = "range", value = [1, "{max_port}"] },
+    { type = "pattern", value = "^[0-9]{1,5}$" },
+]
+"#,
+            RulesFormat::Toml,
+        )
+        .unwrap();
+
+        let answers = vec![Some("9000".to_string()), None];
+        let config = spec.prompt_config(1, &answers, &base_config());
+        assert_eq!(config.prompt_text.as_deref(), Some("Port (1-9000):"));
+        assert_eq!(
+            config.interaction_config.default_value.as_deref(),
+            Some("9000")
+        );
+        assert!(
+            matches!(config.validation_rules[0].validator_type, ValidatorType::Range(min, max) if min == 1.0 && max == 9000.0)
+        );
+        assert!(
+            matches!(&config.validation_rules[1].validator_type, ValidatorType::Pattern(p) if p == "^[0-9]{1,5}$")
+        );
+
+        // Answers that make a rule unusable reject the field's input instead
+        for answer in ["0", "abc"] {
+            let answers = vec![Some(answer.to_string()), None];
+            let config = spec.prompt_config(1, &answers, &base_config());
+            assert!(
+                matches!(&config.validation_rules[0].validator_type, ValidatorType::Unresolved(m) if m.contains("answer to 'max_port'"))
+            );
+        }
+
+        // Answers are matched literally in patterns
+        let spec = parse_form_spec(
+            "fields:\n  - name: prefix\n  - name: id\n    rules: [{ type: pattern, value: '^{prefix}-' }]\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap();
+        for (answer, pattern) in [("a.b", r"^a\.b-"), ("a(b", r"^a\(b-"), ("42", "^42-")] {
+            let answers = vec![Some(answer.to_string()), None];
+            let config = spec.prompt_config(1, &answers, &base_config());
+            assert!(
+                matches!(&config.validation_rules[0].validator_type, ValidatorType::Pattern(p) if p == pattern)
+            );
+        }
+
+        let err = parse_form_spec(
+            "fields:\n  - name: a\n    rules: [{ type: max_length, value: '{b}' }]\n  - name: b\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.contains("rule 1 refers to 'b'"));
+    }
+}
@@ -1,5 +1,9 @@
 pub mod args;
+pub mod choices;
 pub mod config;
+pub mod form;
+pub mod rules;
 
 pub use args::*;
 pub use config::*;
+pub use form::*;
@@ -0,0 +1,340 @@
+use crate::error::{PromptError, Result};
+use crate::validation::rules::ChoiceGroup;
+use crate::validation::{ChoiceOption, ValidationRuleConfig, ValidatorType};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Top-level layout of a rules file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<ValidationRuleConfig>,
+}
+
+/// Supported rules and form spec file formats, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulesFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl RulesFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Load validation rules from a TOML, YAML or JSON file
+pub fn load_rules_file(path: &Path) -> Result<Vec<ValidationRuleConfig>> {
+    let format = RulesFormat::from_path(path).ok_or_else(|| {
+        PromptError::RulesFile(format!(
+            "{}: unsupported file type (expected .toml, .yaml, .yml or .json)",
+            path.display()
+        ))
+    })?;
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| PromptError::RulesFile(format!("{}: {}", path.display(), e)))?;
+
+    parse_rules(&contents, format)
+        .map_err(|message| PromptError::RulesFile(format!("{}: {}", path.display(), message)))
+}
+
+/// Parse rules from file contents; errors include the parser's line information
+pub fn parse_rules(
+    contents: &str,
+    format: RulesFormat,
+) -> std::result::Result<Vec<ValidationRuleConfig>, String> {
+    let file: RulesFile = deserialize_document(contents, format)?;
+
+    for (i, rule) in file.rules.iter().enumerate() {
+        check_rule(rule).map_err(|message| format!("rule {}: {}", i + 1, message))?;
+    }
+
+    Ok(file.rules)
+}
+
+/// Deserialize a TOML, YAML or JSON document
+pub(crate) fn deserialize_document<T: DeserializeOwned>(
+    contents: &str,
+    format: RulesFormat,
+) -> std::result::Result<T, String> {
+    match format {
+        RulesFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+        RulesFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+        RulesFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+    }
+}
+
+/// Catch rule values that deserialize fine but could never be used
+pub(crate) fn check_rule(rule: &ValidationRuleConfig) -> std::result::Result<(), String> {
+    match &rule.validator_type {
+        ValidatorType::Pattern(pattern) => Regex::new(pattern)
+            .map(|_| ())
+            .map_err(|e| format!("invalid pattern: {}", e)),
+        ValidatorType::Range(min, max) if min >= max => Err(format!(
+            "range minimum ({}) must be less than maximum ({})",
+            min, max
+        )),
+        ValidatorType::Choices(choices) if choices.is_empty() => {
+            Err("choices list cannot be empty".to_string())
+        }
+        ValidatorType::Choices(choices) => check_choices(choices, &rule.parameters),
+        _ => Ok(()),
+    }
+}
+
+/// Check that group limits name real groups and that locked choices can
+/// always be selected
+fn check_choices(
+    choices: &[ChoiceOption],
+    parameters: &HashMap<String, String>,
+) -> std::result::Result<(), String> {
+    ChoiceGroup::from_choices(choices, parameters)?;
+
+    if let Some(choice) = choices
+        .iter()
+        .find(|choice| choice.locked && choice.disabled.is_some())
+    {
+        return Err(format!(
+            "choice '{}' cannot be both locked and disabled",
+            choice.value
+        ));
+    }
+
+    let locked = choices.iter().filter(|choice| choice.locked).count();
+    let max_choices = parameters
+        .get("max_choices")
+        .and_then(|max| max.parse::<usize>().ok())
+        .unwrap_or(1);
+    if locked > 0 && max_choices < 2 {
+        return Err("locked choices need a multiple-choice menu (max_choices above 1)".to_string());
+    }
+    if locked > max_choices {
+        return Err(format!(
+            "{} locked choices exceed max_choices ({})",
+            locked, max_choices
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Priority;
+
+    #[test]
+    fn test_parse_toml_rules() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+type = "required"
+message = "Username is required"
+
+[[rules]]
+type = "min_length"
+value = 3
+priority = "low"
+
+[[rules]]
+type = "range"
+value = [1, 100]
+
+[[rules]]
+type = "date"
+
+[[rules]]
+type = "choices"
+value = ["dev", "prod"]
+parameters = { max_choices = 2, case_sensitive = true }
+"#,
+            RulesFormat::Toml,
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 5);
+        assert!(matches!(rules[0].validator_type, ValidatorType::Required));
+        assert_eq!(
+            rules[0].custom_message.as_deref(),
+            Some("Username is required")
+        );
+        assert!(matches!(
+            rules[1].validator_type,
+            ValidatorType::MinLength(3)
+        ));
+        assert_eq!(rules[1].priority, Some(Priority::Low));
+        assert!(
+            matches!(rules[2].validator_type, ValidatorType::Range(min, max) if min == 1.0 && max == 100.0)
+        );
+        assert!(matches!(rules[3].validator_type, ValidatorType::Date(None)));
+        assert_eq!(rules[4].parameters.get("max_choices").unwrap(), "2");
+        assert_eq!(rules[4].parameters.get("case_sensitive").unwrap(), "true");
+    }
+
+    #[test]
+    fn test_parse_yaml_and_json_rules() {
+        let yaml = parse_rules(
+            "rules:\n  - type: email\n  - type: pattern\n    value: '^[a-z]+@'\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap();
+        assert_eq!(yaml.len(), 2);
+        assert!(matches!(yaml[0].validator_type, ValidatorType::Email));
+
+        let json = parse_rules(
+            r#"{"rules": [{"type": "datetime", "value": "%Y-%m-%d %H:%M"}]}"#,
+            RulesFormat::Json,
+        )
+        .unwrap();
+        assert!(
+            matches!(&json[0].validator_type, ValidatorType::DateTime(Some(f)) if f == "%Y-%m-%d %H:%M")
+        );
+    }
+
+    #[test]
+    fn test_parse_labelled_choices() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+type = "choices"
+value = [
+    "dev",
+    { value = "prod", label = "Production", description = "us-east-1" },
+    { value = "staging", disabled = "deploy freeze until Friday" },
+    { value = "core", locked = true },
+]
+parameters = { max_choices = 3 }
+"#,
+            RulesFormat::Toml,
+        )
+        .unwrap();
+
+        let ValidatorType::Choices(choices) = &rules[0].validator_type else {
+            panic!("expected choices rule");
+        };
+        assert_eq!(choices[0].display_label(), "dev");
+        assert_eq!(choices[1].value, "prod");
+        assert_eq!(choices[1].display_label(), "Production");
+        assert_eq!(choices[1].description.as_deref(), Some("us-east-1"));
+        assert_eq!(
+            choices[2].disabled.as_deref(),
+            Some("deploy freeze until Friday")
+        );
+        assert!(choices[3].locked);
+
+        let err = parse_rules(
+            r#"{"rules": [{"type": "choices", "value": [{"value": "core", "locked": true}, "extra"]}]}"#,
+            RulesFormat::Json,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("locked choices need a multiple-choice menu"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_line() {
+        let err = parse_rules(
+            "[[rules]]\ntype = \"required\"\n\n[[rules]]\ntype = \"no_such_rule\"\n",
+            RulesFormat::Toml,
+        )
+        .unwrap_err();
+        assert!(err.contains("at line 5"), "{}", err);
+
+        let err = parse_rules(
+            "[[rules]]\ntype = \"min_length\"\nvalue = 3\nmesage = \"too short\"\n",
+            RulesFormat::Toml,
+        )
+        .unwrap_err();
+        assert!(err.contains("at line 4"), "{}", err);
+
+        let err = parse_rules(
+            "rules:\n  - type: required\n  - type: min_length\n    value: many\n",
+            RulesFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.contains("at line 4"), "{}", err);
+
+        let err = parse_rules(
+            "{\"rules\": [\n  {\"type\": \"required\"},\n  {\"type\": \"pattern\",\n   \"value\": 3}\n]}",
+            RulesFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.contains("at line 4"), "{}", err);
+    }
+
+    #[test]
+    fn test_rule_keys_in_any_order() {
+        let rules = parse_rules(
+            r#"{"rules": [{"message": "3+", "value": 3, "type": "min_length"}]}"#,
+            RulesFormat::Json,
+        )
+        .unwrap();
+        assert!(matches!(
+            rules[0].validator_type,
+            ValidatorType::MinLength(3)
+        ));
+        assert_eq!(rules[0].custom_message.as_deref(), Some("3+"));
+
+        let err = parse_rules(
+            "[[rules]]\nvalue = 3\nmessage = \"3+\"\n",
+            RulesFormat::Toml,
+        )
+        .unwrap_err();
+        assert!(err.contains("missing field `type`"), "{}", err);
+
+        // Unknown types are caught before the rule is built, from a list
+        // that must name every rule type
+        let derived = serde_json::from_str::<ValidatorType>(r#"{"type": "nope"}"#)
+            .unwrap_err()
+            .to_string();
+        let expected = derived.split(" at line").next().unwrap();
+        let err = parse_rules(r#"{"rules": [{"type": "nope"}]}"#, RulesFormat::Json).unwrap_err();
+        assert!(err.starts_with(expected), "{} / {}", err, expected);
+    }
+
+    #[test]
+    fn test_invalid_rule_values_rejected() {
+        let err = parse_rules(
+            "[[rules]]\ntype = \"pattern\"\nvalue = \"[\"\n",
+            RulesFormat::Toml,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("rule 1: invalid pattern"));
+
+        let err = parse_rules(
+            "[[rules]]\ntype = \"range\"\nvalue = [10, 1]\n",
+            RulesFormat::Toml,
+        )
+        .unwrap_err();
+        assert!(err.contains("must be less than"));
+    }
+
+    #[test]
+    fn test_rules_format_from_path() {
+        assert_eq!(
+            RulesFormat::from_path(Path::new("rules.toml")),
+            Some(RulesFormat::Toml)
+        );
+        assert_eq!(
+            RulesFormat::from_path(Path::new("rules.YML")),
+            Some(RulesFormat::Yaml)
+        );
+        assert_eq!(RulesFormat::from_path(Path::new("rules.txt")), None);
+    }
+}
@@ -31,6 +31,24 @@ pub enum PromptError {
 
     #[error("Date/time parsing error: {0}")]
     DateTime(#[from] chrono::ParseError),
+
+    #[error("Validation command could not be run: {0}")]
+    CommandSpawn(String),
+
+    #[error("Invalid rules file {0}")]
+    RulesFile(String),
+
+    #[error("Invalid form spec {0}")]
+    FormSpec(String),
+
+    #[error("Editor could not be run: {0}")]
+    Editor(String),
+
+    #[error("Could not load choices from {0}")]
+    ChoicesSource(String),
+
+    #[error("Returned to previous field")]
+    NavigateBack,
 }
 
 pub type Result<T> = std::result::Result<T, PromptError>;
@@ -39,7 +57,10 @@ impl PromptError {
     pub fn exit_code(&self) -> i32 {
         match self {
             Self::ValidationFailed(_) => 1,
-            Self::InvalidArguments(_) => 2,
+            Self::InvalidArguments(_)
+            | Self::RulesFile(_)
+            | Self::FormSpec(_)
+            | Self::ChoicesSource(_) => 2,
             Self::MaxAttemptsExceeded => 3,
             Self::Timeout => 124,
             Self::Interrupted => 130,
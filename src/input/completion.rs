@@ -0,0 +1,346 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a `--complete-cmd` script may run before its candidates are dropped
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where Tab completion finds candidates
+#[derive(Debug, Clone)]
+pub enum CompletionSource {
+    /// Choice values. With a separator, the text after its last occurrence is
+    /// completed, so each of several selections can be completed in turn.
+    Choices {
+        choices: Vec<String>,
+        separator: Option<String>,
+        case_sensitive: bool,
+    },
+    /// Filesystem paths, optionally directories only
+    Paths { dirs_only: bool },
+    /// Lines printed by a shell command, which receives the text before the
+    /// cursor on stdin
+    Command(String),
+}
+
+/// Candidates for the text before the cursor. Each candidate replaces the
+/// text from byte offset `start` onwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+impl CompletionSource {
+    pub fn complete(&self, text: &str) -> Completion {
+        match self {
+            Self::Choices {
+                choices,
+                separator,
+                case_sensitive,
+            } => complete_choice(text, choices, separator.as_deref(), *case_sensitive),
+            Self::Paths { dirs_only } => Completion {
+                start: 0,
+                candidates: complete_path(text, *dirs_only),
+            },
+            Self::Command(command) => Completion {
+                start: 0,
+                candidates: run_completion_command(command, text),
+            },
+        }
+    }
+}
+
+/// The longest prefix shared by all candidates
+pub fn common_prefix(candidates: &[String]) -> &str {
+    let Some(first) = candidates.first() else {
+        return "";
+    };
+    let mut prefix = first.as_str();
+    for candidate in &candidates[1..] {
+        let len = prefix
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(candidate.len()), |((i, _), _)| i);
+        prefix = &prefix[..len];
+    }
+    prefix
+}
+
+/// The input a fish-style ghost suggestion would complete `text` to: the most
+/// recent matching history entry, then a matching choice, then the default
+/// value. Only the default is suggested for empty input.
+pub fn suggest(
+    text: &str,
+    history: &[String],
+    choices: Option<&CompletionSource>,
+    default: Option<&str>,
+) -> Option<String> {
+    let extends = |candidate: &str| candidate.len() > text.len() && candidate.starts_with(text);
+    let from_history = || {
+        history
+            .iter()
+            .rev()
+            .find(|entry| extends(entry.as_str()))
+            .cloned()
+    };
+    let from_choices = || {
+        let source @ CompletionSource::Choices { .. } = choices? else {
+            return None;
+        };
+        let completion = source.complete(text);
+        completion
+            .candidates
+            .iter()
+            .map(|candidate| format!("{}{}", &text[..completion.start], candidate))
+            .find(|completed| completed.chars().count() > text.chars().count())
+    };
+
+    if text.is_empty() {
+        return default.filter(|value| !value.is_empty()).map(String::from);
+    }
+    from_history()
+        .or_else(from_choices)
+        .or_else(|| default.filter(|value| extends(value)).map(String::from))
+}
+
+fn complete_choice(
+    text: &str,
+    choices: &[String],
+    separator: Option<&str>,
+    case_sensitive: bool,
+) -> Completion {
+    let segment_start = separator
+        .filter(|sep| !sep.is_empty())
+        .and_then(|sep| text.rfind(sep).map(|i| i + sep.len()))
+        .unwrap_or(0);
+    let segment = &text[segment_start..];
+    let typed = segment.trim_start();
+    let start = segment_start + (segment.len() - typed.len());
+
+    let candidates = choices
+        .iter()
+        .filter(|choice| {
+            if case_sensitive {
+                choice.starts_with(typed)
+            } else {
+                choice.to_lowercase().starts_with(&typed.to_lowercase())
+            }
+        })
+        .cloned()
+        .collect();
+    Completion { start, candidates }
+}
+
+/// Entries of the directory named by `text` up to its last `/` whose names
+/// start with the rest. Directories end in `/`; hidden entries are only
+/// offered once a `.` has been typed.
+fn complete_path(text: &str, dirs_only: bool) -> Vec<String> {
+    let (dir_part, name_prefix) = match text.rfind('/') {
+        Some(i) => text.split_at(i + 1),
+        None => ("", text),
+    };
+    let dir = match dir_part {
+        "" => PathBuf::from("."),
+        _ => expand_home(dir_part),
+    };
+
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(name_prefix)
+                || (name.starts_with('.') && !name_prefix.starts_with('.'))
+            {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if dirs_only && !is_dir {
+                return None;
+            }
+            Some(format!(
+                "{}{}{}",
+                dir_part,
+                name,
+                if is_dir { "/" } else { "" }
+            ))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Run a completion script, returning its non-empty output lines. A script
+/// that fails, times out or cannot be started offers no candidates.
+fn run_completion_command(command: &str, text: &str) -> Vec<String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    let Ok(mut child) = shell
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return Vec::new();
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    // Read output while waiting so a chatty script can't fill the pipe and stall
+    let reader = child.stdout.take().map(|mut stdout| {
+        std::thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stdout.read_to_string(&mut output);
+            output
+        })
+    });
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+        }
+    };
+
+    let output = match (status, reader) {
+        (Some(status), Some(reader)) if status.success() => reader.join().unwrap_or_default(),
+        _ => return Vec::new(),
+    };
+    output
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_complete_choices() {
+        let source = CompletionSource::Choices {
+            choices: strings(&["staging", "stable", "prod"]),
+            separator: Some(",".to_string()),
+            case_sensitive: false,
+        };
+
+        let completion = source.complete("prod, ST");
+        assert_eq!(completion.start, 6);
+        assert_eq!(completion.candidates, strings(&["staging", "stable"]));
+        assert_eq!(common_prefix(&completion.candidates), "sta");
+
+        assert_eq!(source.complete("p").candidates, strings(&["prod"]));
+        assert!(source.complete("x").candidates.is_empty());
+    }
+
+    #[test]
+    fn test_complete_paths() {
+        let dir = std::env::temp_dir().join(format!("askr-complete-{}", std::process::id()));
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::write(dir.join("cargo.toml"), "").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+        let base = format!("{}/", dir.display());
+
+        let completion = CompletionSource::Paths { dirs_only: false }.complete(&base);
+        assert_eq!(
+            completion.candidates,
+            vec![format!("{}cargo.toml", base), format!("{}config/", base)]
+        );
+        let completion = CompletionSource::Paths { dirs_only: true }.complete(&base);
+        assert_eq!(completion.candidates, vec![format!("{}config/", base)]);
+        let completion =
+            CompletionSource::Paths { dirs_only: false }.complete(&format!("{}.h", base));
+        assert_eq!(completion.candidates, vec![format!("{}.hidden", base)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_complete_from_command() {
+        let source = CompletionSource::Command(
+            "read prefix; printf '%s1\\n%s2\\n' \"$prefix\" \"$prefix\"".to_string(),
+        );
+        assert_eq!(
+            source.complete("web").candidates,
+            strings(&["web1", "web2"])
+        );
+
+        let failing = CompletionSource::Command("exit 1".to_string());
+        assert!(failing.complete("web").candidates.is_empty());
+    }
+
+    #[test]
+    fn test_suggest() {
+        let history = strings(&["prod-eu", "staging-2", "prod-us"]);
+        let choices = CompletionSource::Choices {
+            choices: strings(&["qa", "prod-ap"]),
+            separator: None,
+            case_sensitive: true,
+        };
+
+        assert_eq!(
+            suggest("pr", &history, Some(&choices), Some("prod")),
+            Some("prod-us".to_string())
+        );
+        assert_eq!(
+            suggest("q", &history, Some(&choices), None),
+            Some("qa".to_string())
+        );
+        assert_eq!(
+            suggest("", &history, Some(&choices), Some("prod")),
+            Some("prod".to_string())
+        );
+        assert_eq!(suggest("prod-us", &history, None, None), None);
+        assert_eq!(
+            suggest(
+                "x",
+                &history,
+                Some(&CompletionSource::Paths { dirs_only: false }),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        assert_eq!(common_prefix(&strings(&["config/", "cargo.toml"])), "c");
+        assert_eq!(common_prefix(&strings(&["ab", "abc"])), "ab");
+        assert_eq!(common_prefix(&strings(&["héllo", "hélp"])), "hél");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
@@ -0,0 +1,184 @@
+use super::graphemes::grapheme_count;
+
+/// Text and cursor position (in grapheme clusters) of the line being edited
+pub type Snapshot = (String, usize);
+
+/// Killed entries kept for yanking, as in Emacs and readline
+const KILL_RING_SIZE: usize = 32;
+
+/// Earlier states of the line for undo and redo. A run of typed characters
+/// is undone in one step.
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    typing: bool,
+}
+
+impl UndoStack {
+    /// Record the state before a key was handled. Keys that left the text
+    /// unchanged, such as cursor moves, are not recorded but end a run of
+    /// typing.
+    pub fn record(&mut self, before: Snapshot, after: &str, typed: bool) {
+        if before.0 == after {
+            self.typing = false;
+            return;
+        }
+        if !(typed && self.typing) {
+            self.undo.push(before);
+        }
+        self.typing = typed;
+        self.redo.clear();
+    }
+
+    /// Step back, returning the state to restore
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.typing = false;
+        Some(previous)
+    }
+
+    /// Step forward again after an undo
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.typing = false;
+        Some(next)
+    }
+}
+
+/// What the previous key did, which decides whether kills append and
+/// whether a yank can be rotated
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum LastCommand {
+    #[default]
+    Other,
+    Kill,
+    Yank {
+        start: usize,
+        len: usize,
+    },
+}
+
+/// Killed text available to yank back. Consecutive kills join into one
+/// entry; positions count grapheme clusters.
+#[derive(Debug, Default)]
+pub struct KillRing {
+    entries: Vec<String>, // Oldest first
+    rotation: usize,      // How far back from the newest the last yank reached
+    previous: LastCommand,
+    current: LastCommand,
+}
+
+impl KillRing {
+    /// Call before handling each key, so kills and yanks know what came
+    /// just before them
+    pub fn start_key(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// Save killed text. Text killed backwards (before the cursor) is
+    /// prepended when it continues a previous kill, otherwise appended.
+    pub fn kill(&mut self, text: &str, backwards: bool) {
+        if text.is_empty() {
+            return;
+        }
+        match self.entries.last_mut() {
+            Some(last) if self.previous == LastCommand::Kill => {
+                if backwards {
+                    last.insert_str(0, text);
+                } else {
+                    last.push_str(text);
+                }
+            }
+            _ => {
+                self.entries.push(text.to_string());
+                if self.entries.len() > KILL_RING_SIZE {
+                    self.entries.remove(0);
+                }
+            }
+        }
+        self.current = LastCommand::Kill;
+    }
+
+    /// The newest kill, to be inserted at cluster position `at`
+    pub fn yank(&mut self, at: usize) -> Option<String> {
+        let text = self.entries.last()?.clone();
+        self.rotation = 0;
+        self.current = LastCommand::Yank {
+            start: at,
+            len: grapheme_count(&text),
+        };
+        Some(text)
+    }
+
+    /// Straight after a yank, swap the yanked text for the next older kill.
+    /// Returns the start and length of the text to replace and its
+    /// replacement.
+    pub fn yank_pop(&mut self) -> Option<(usize, usize, String)> {
+        let LastCommand::Yank { start, len } = self.previous else {
+            return None;
+        };
+        self.rotation = (self.rotation + 1) % self.entries.len();
+        let text = self.entries[self.entries.len() - 1 - self.rotation].clone();
+        self.current = LastCommand::Yank {
+            start,
+            len: grapheme_count(&text),
+        };
+        Some((start, len, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_groups_typing() {
+        let mut stack = UndoStack::default();
+        stack.record((String::new(), 0), "a", true);
+        stack.record(("a".to_string(), 1), "ab", true);
+        // A cursor move ends the run of typing
+        stack.record(("ab".to_string(), 2), "ab", false);
+        stack.record(("ab".to_string(), 1), "acb", true);
+
+        let state = stack.undo(("acb".to_string(), 2)).unwrap();
+        assert_eq!(state, ("ab".to_string(), 1));
+        let state = stack.undo(state).unwrap();
+        assert_eq!(state, (String::new(), 0));
+        assert!(stack.undo(state.clone()).is_none());
+
+        let state = stack.redo(state).unwrap();
+        assert_eq!(state.0, "ab");
+        // A new edit drops the redo history
+        stack.record(state, "abX", true);
+        assert!(stack.redo(("abX".to_string(), 3)).is_none());
+    }
+
+    #[test]
+    fn test_kill_ring_appends_and_rotates() {
+        let mut ring = KillRing::default();
+        ring.start_key();
+        ring.kill("first", false);
+        ring.start_key();
+        ring.start_key();
+        ring.kill("world", false);
+        // Consecutive kills join, backwards ones in front
+        ring.start_key();
+        ring.kill("hello ", true);
+        assert_eq!(ring.entries, ["first", "hello world"]);
+
+        ring.start_key();
+        assert_eq!(ring.yank(3).as_deref(), Some("hello world"));
+        ring.start_key();
+        assert_eq!(ring.yank_pop(), Some((3, 11, "first".to_string())));
+        ring.start_key();
+        assert_eq!(ring.yank_pop(), Some((3, 5, "hello world".to_string())));
+
+        // Rotating only works straight after a yank
+        ring.start_key();
+        ring.start_key();
+        assert_eq!(ring.yank_pop(), None);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::error::{PromptError, Result};
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The user's editor: `$VISUAL`, then `$EDITOR`, then a platform default
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+/// Let the user edit `text` in their editor through a temporary file. The
+/// final newline editors add is dropped. Returns `None` when the editor
+/// exits unsuccessfully (e.g. vim's `:cq`), so the edit can be abandoned.
+pub fn edit_text(text: &str) -> Result<Option<String>> {
+    edit_with(&editor_command(), text)
+}
+
+fn edit_with(editor: &str, text: &str) -> Result<Option<String>> {
+    let dir = PrivateDir::create()?;
+    let path = dir.0.join("askr-edit.txt");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(text.as_bytes())?;
+
+    if !run_editor(editor, &path)? {
+        return Ok(None);
+    }
+    let edited = fs::read_to_string(&path)?;
+    let edited = edited
+        .strip_suffix('\n')
+        .map(|rest| rest.strip_suffix('\r').unwrap_or(rest))
+        .unwrap_or(&edited);
+    Ok(Some(edited.to_string()))
+}
+
+/// A temporary directory only the current user can read, with an
+/// unpredictable name, removed with everything in it when dropped. The
+/// answer being edited may be a secret, and editors leave swap and backup
+/// files next to the file they edit.
+struct PrivateDir(PathBuf);
+
+impl PrivateDir {
+    fn create() -> Result<Self> {
+        let mut attempts = 0;
+        loop {
+            let path = std::env::temp_dir().join(format!("askr-edit-{:016x}", random_u64()));
+            let mut builder = fs::DirBuilder::new();
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            // `create` fails rather than reusing anything already there,
+            // including a planted symlink
+            match builder.create(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 16 => {
+                    attempts += 1
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A fresh random number from the standard library's randomly seeded hasher
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    if let Ok(elapsed) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Run the editor on `path` attached to the terminal, even when askr's own
+/// stdin or stdout is redirected. The editor string may include arguments,
+/// as in `code --wait`.
+fn run_editor(editor: &str, path: &Path) -> Result<bool> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command
+            .arg("/C")
+            .arg(format!("{} \"{}\"", editor, path.display()));
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(path);
+        command
+    };
+
+    #[cfg(unix)]
+    if let Ok(tty) = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+    {
+        command
+            .stdin(std::process::Stdio::from(tty.try_clone()?))
+            .stdout(std::process::Stdio::from(tty));
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| PromptError::Editor(format!("{}: {}", editor, e)))?;
+    // The shell exits with 127 when the editor itself can't be found
+    if cfg!(unix) && status.code() == Some(127) {
+        return Err(PromptError::Editor(format!(
+            "{}: command not found",
+            editor
+        )));
+    }
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_with_editor() {
+        let edited = edit_with("printf 'key: value\\nlist: [1, 2]\\n' >", "draft").unwrap();
+        assert_eq!(edited.as_deref(), Some("key: value\nlist: [1, 2]"));
+
+        // A failing editor abandons the edit
+        assert_eq!(edit_with("false", "draft").unwrap(), None);
+        assert!(edit_with("askr-no-such-editor", "draft").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_file_is_private_and_removed() {
+        // The editor replaces the text with the modes of the file and its
+        // directory, then the directory's path
+        let report = edit_with(
+            r#"f() { { ls -ld "$1" "${1%/*}"; echo "${1%/*}"; } > "$1.tmp"; mv "$1.tmp" "$1"; }; f"#,
+            "secret",
+        )
+        .unwrap()
+        .unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("drwx------"), "{}", lines[0]);
+        assert!(lines[1].starts_with("-rw-------"), "{}", lines[1]);
+        assert!(!Path::new(lines[2]).exists());
+    }
+}
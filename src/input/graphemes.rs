@@ -0,0 +1,87 @@
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Number of grapheme clusters (user-perceived characters) in `text`. The
+/// line editors count cursor positions in these, so an emoji ZWJ sequence
+/// or a letter with combining accents moves and deletes as one character.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Byte offset where the grapheme cluster at `index` starts, or the end of
+/// the text for an index past the last cluster
+pub fn byte_offset(text: &str, index: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(index)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// Insert `inserted` before the cluster at `index` and return the index just
+/// after it. Text that joins a neighbouring cluster, like a combining accent
+/// typed after its letter, leaves the index after the joined cluster.
+pub fn insert_at(text: &mut String, index: usize, inserted: &str) -> usize {
+    let at = byte_offset(text, index);
+    text.insert_str(at, inserted);
+    grapheme_count(&text[..at + inserted.len()])
+}
+
+/// Remove the clusters in `range`, returning the removed text
+pub fn remove_range(text: &mut String, range: Range<usize>) -> String {
+    let start = byte_offset(text, range.start);
+    let end = byte_offset(text, range.end);
+    text.drain(start..end).collect()
+}
+
+/// Swap the clusters before and at `index`, as CTRL+T does. At the end of
+/// the text the last two are swapped. Returns the index after the swapped
+/// pair, or `None` when there is nothing to swap.
+pub fn transpose(text: &mut String, index: usize) -> Option<usize> {
+    let mut clusters: Vec<&str> = text.graphemes(true).collect();
+    let swap_at = index.min(clusters.len().saturating_sub(1));
+    if swap_at == 0 {
+        return None;
+    }
+    clusters.swap(swap_at - 1, swap_at);
+    *text = clusters.concat();
+    Some(swap_at + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Family emoji (a ZWJ sequence), "e" + combining acute, and wide CJK
+    const MIXED: &str = "a👨\u{200d}👩\u{200d}👧e\u{301}日本";
+
+    #[test]
+    fn test_clusters_in_mixed_input() {
+        assert_eq!(grapheme_count(MIXED), 5);
+        assert_eq!(byte_offset(MIXED, 2), 1 + "👨\u{200d}👩\u{200d}👧".len());
+        assert_eq!(byte_offset(MIXED, 9), MIXED.len());
+
+        let mut text = MIXED.to_string();
+        assert_eq!(
+            remove_range(&mut text, 1..3),
+            "👨\u{200d}👩\u{200d}👧e\u{301}"
+        );
+        assert_eq!(text, "a日本");
+    }
+
+    #[test]
+    fn test_insert_joins_clusters() {
+        let mut text = "cafe".to_string();
+        // A combining accent joins the "e" before it; the cursor stays after it
+        assert_eq!(insert_at(&mut text, 4, "\u{301}"), 4);
+        assert_eq!(text, "cafe\u{301}");
+        assert_eq!(insert_at(&mut text, 0, "日"), 1);
+        assert_eq!(grapheme_count(&text), 5);
+    }
+
+    #[test]
+    fn test_transpose_clusters() {
+        let mut text = "e\u{301}日".to_string();
+        assert_eq!(transpose(&mut text, 2), Some(2));
+        assert_eq!(text, "日e\u{301}");
+        assert_eq!(transpose(&mut "x".to_string(), 1), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Entries kept per history key unless `--history-size` says otherwise
+pub const DEFAULT_HISTORY_SIZE: usize = 500;
+
+/// Previously submitted answers for one prompt, oldest first. Each history key
+/// gets its own file under `$XDG_STATE_HOME/askr/history/` (falling back to
+/// `~/.local/state`), holding one JSON string per line.
+#[derive(Debug, Clone)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+    max_entries: usize,
+}
+
+impl History {
+    /// Load the history stored under `key`. A missing or unreadable file
+    /// gives an empty history; recall is a convenience, never a failure.
+    pub fn load(key: &str, max_entries: usize) -> Self {
+        let path = history_dir().map(|dir| dir.join(file_name(key)));
+        let entries = path.as_deref().map(read_entries).unwrap_or_default();
+        Self::with_entries(path, entries, max_entries)
+    }
+
+    fn with_entries(path: Option<PathBuf>, entries: Vec<String>, max_entries: usize) -> Self {
+        let mut history = Self {
+            path,
+            entries: Vec::new(),
+            max_entries,
+        };
+        for entry in entries {
+            history.add(&entry);
+        }
+        history
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Record an answer as the most recent entry, dropping any earlier copy
+    /// and the oldest entries beyond the size limit
+    pub fn add(&mut self, entry: &str) {
+        if entry.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|existing| existing != entry);
+        self.entries.push(entry.to_string());
+        let excess = self.entries.len().saturating_sub(self.max_entries);
+        self.entries.drain(..excess);
+    }
+
+    /// Write the history back to its file, creating the directory if needed
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        fs::write(path, contents)
+    }
+}
+
+/// Directory holding history files
+fn history_dir() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("askr").join("history"))
+}
+
+/// Map a history key to a safe file name: the key with unsafe characters
+/// replaced, for people browsing the directory, then a hash of the exact key,
+/// so keys that differ only in those characters (or in case, on
+/// case-insensitive file systems) never share a file
+fn file_name(key: &str) -> String {
+    let readable: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    format!(
+        "{}-{:016x}.jsonl",
+        readable.trim_start_matches('.'),
+        fnv1a(key.as_bytes())
+    )
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher gives the same
+/// value in every build, so a key keeps finding its file
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Read entries, skipping lines that are not JSON strings
+fn read_entries(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str::<String>(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_dedup_and_limit() {
+        let mut history = History::with_entries(None, Vec::new(), 3);
+        for entry in ["db1", "db2", "db1", "", "db3", "db4"] {
+            history.add(entry);
+        }
+        assert_eq!(history.entries(), ["db1", "db3", "db4"]);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("askr-history-{}", std::process::id()))
+            .join(file_name("deploy/host name"));
+        let mut history = History::with_entries(Some(path.clone()), Vec::new(), 10);
+        history.add("web-1.example.com");
+        history.add("line\nbreak");
+        history.save().unwrap();
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("deploy_host_name-"), "{}", name);
+        assert_eq!(
+            read_entries(&path),
+            vec!["web-1.example.com", "line\nbreak"]
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_history_keys_never_share_a_file() {
+        let names: std::collections::HashSet<String> = [
+            "deploy/host",
+            "deploy host",
+            "deploy_host",
+            "Deploy_host",
+            ".deploy_host",
+        ]
+        .into_iter()
+        .map(file_name)
+        .collect();
+        assert_eq!(names.len(), 5);
+        // The same key always maps to the same file
+        assert_eq!(file_name("deploy/host"), file_name("deploy/host"));
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod completion;
+pub mod editing;
+pub mod editor;
+pub mod graphemes;
+pub mod history;
 pub mod interactive;
+pub mod paste;
 pub mod processor;
 pub mod stdin;
+pub mod text_area;
+pub mod words;
 
 // TODO: Implement input modules
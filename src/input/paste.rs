@@ -0,0 +1,61 @@
+use crate::cli::args::PasteMode;
+
+/// Pasted text ready for a single-line prompt
+#[derive(Debug, PartialEq)]
+pub enum Paste {
+    Line(String),  // Text to insert at the cursor
+    Lines(String), // Several lines, to continue in the multi-line editor
+    Rejected,
+}
+
+/// Use `\n` for every line break; terminals send pasted newlines as `\r`
+pub fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Prepare pasted text for a single-line prompt. Trailing line breaks are
+/// dropped so that pasting a copied line doesn't count as pressing Enter;
+/// `mode` decides what happens to any others.
+pub fn paste_line(text: &str, mode: PasteMode) -> Paste {
+    let text = normalize_newlines(text);
+    let text = text.trim_end_matches('\n');
+    if !text.contains('\n') {
+        return Paste::Line(text.to_string());
+    }
+    match mode {
+        PasteMode::Multiline => Paste::Lines(text.to_string()),
+        PasteMode::Join => Paste::Line(
+            text.split('\n')
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        PasteMode::Reject => Paste::Rejected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste_line() {
+        // A copied line loses its newline in every mode
+        let token = "ghp_abc123\r\n";
+        for mode in [PasteMode::Multiline, PasteMode::Join, PasteMode::Reject] {
+            assert_eq!(paste_line(token, mode), Paste::Line("ghp_abc123".into()));
+        }
+
+        let block = "first\rsecond\r\n  third\n";
+        assert_eq!(
+            paste_line(block, PasteMode::Multiline),
+            Paste::Lines("first\nsecond\n  third".into())
+        );
+        assert_eq!(
+            paste_line(block, PasteMode::Join),
+            Paste::Line("first second third".into())
+        );
+        assert_eq!(paste_line(block, PasteMode::Reject), Paste::Rejected);
+    }
+}
@@ -0,0 +1,263 @@
+use super::graphemes::{self, grapheme_count};
+
+/// Editable multi-line text with a (row, column) cursor. Columns count
+/// grapheme clusters, so a cursor never splits an emoji sequence or a
+/// letter from its accents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+        }
+    }
+}
+
+impl TextArea {
+    /// Start from existing text with the cursor at its end
+    pub fn from_text(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(String::from).collect();
+        let row = lines.len() - 1;
+        let col = grapheme_count(&lines[row]);
+        Self { lines, row, col }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.len() == 1 && self.lines[0].is_empty()
+    }
+
+    pub fn on_first_row(&self) -> bool {
+        self.row == 0
+    }
+
+    pub fn on_last_row(&self) -> bool {
+        self.row + 1 == self.lines.len()
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let line = &mut self.lines[self.row];
+        self.col = graphemes::insert_at(line, self.col, c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Insert text at the cursor, such as a paste, starting a new line at
+    /// each `\n`
+    pub fn insert_text(&mut self, text: &str) {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.insert_newline();
+            }
+            self.col = graphemes::insert_at(&mut self.lines[self.row], self.col, line);
+        }
+    }
+
+    /// Split the current line at the cursor
+    pub fn insert_newline(&mut self) {
+        let at = self.byte_index(self.col);
+        let rest = self.lines[self.row].split_off(at);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Delete the character before the cursor, joining lines at a line start
+    pub fn backspace(&mut self) -> bool {
+        if self.col > 0 {
+            self.col -= 1;
+            graphemes::remove_range(&mut self.lines[self.row], self.col..self.col + 1);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Delete the character under the cursor, joining lines at a line end
+    pub fn delete(&mut self) -> bool {
+        if self.col < self.line_len() {
+            graphemes::remove_range(&mut self.lines[self.row], self.col..self.col + 1);
+        } else if !self.on_last_row() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Move left, continuing at the end of the previous line
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    /// Move right, continuing at the start of the next line
+    pub fn move_right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if !self.on_last_row() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if !self.on_last_row() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn line_start(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn line_end(&mut self) {
+        self.col = self.line_len();
+    }
+
+    /// Delete from the cursor to the end of the line
+    pub fn kill_to_line_end(&mut self) -> bool {
+        let at = self.byte_index(self.col);
+        let changed = at < self.lines[self.row].len();
+        self.lines[self.row].truncate(at);
+        changed
+    }
+
+    /// Delete from the start of the line to the cursor
+    pub fn kill_to_line_start(&mut self) -> bool {
+        let at = self.byte_index(self.col);
+        self.lines[self.row].drain(..at);
+        self.col = 0;
+        at > 0
+    }
+
+    fn line_len(&self) -> usize {
+        grapheme_count(&self.lines[self.row])
+    }
+
+    fn byte_index(&self, col: usize) -> usize {
+        graphemes::byte_offset(&self.lines[self.row], col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_area_editing() {
+        let mut area = TextArea::default();
+        for c in "Fixes".chars() {
+            area.insert_char(c);
+        }
+        area.insert_newline();
+        area.insert_newline();
+        for c in "- naïve".chars() {
+            area.insert_char(c);
+        }
+        assert_eq!(area.text(), "Fixes\n\n- naïve");
+        assert_eq!(area.cursor(), (2, 7));
+
+        // Backspace at a line start joins it onto the line above
+        area.line_start();
+        assert!(area.backspace());
+        assert_eq!(area.text(), "Fixes\n- naïve");
+        assert_eq!(area.cursor(), (1, 0));
+
+        area.move_up();
+        area.line_end();
+        assert!(area.delete());
+        assert_eq!(area.text(), "Fixes- naïve");
+
+        area.move_left();
+        area.move_left();
+        assert!(area.kill_to_line_end());
+        assert_eq!(area.text(), "Fix");
+        assert!(!area.delete());
+    }
+
+    #[test]
+    fn test_text_area_cursor_movement() {
+        let mut area = TextArea::from_text("ssh-ed25519 AAAA\nsecond\nx");
+        assert_eq!(area.cursor(), (2, 1));
+
+        // Vertical moves clamp the column to the shorter line
+        area.move_up();
+        area.line_end();
+        area.move_down();
+        assert_eq!(area.cursor(), (2, 1));
+
+        // Horizontal moves cross line boundaries
+        area.line_start();
+        area.move_left();
+        assert_eq!(area.cursor(), (1, 6));
+        area.move_right();
+        assert_eq!(area.cursor(), (2, 0));
+        assert!(area.on_last_row());
+        assert!(TextArea::default().is_empty());
+    }
+
+    #[test]
+    fn test_text_area_clusters() {
+        let mut area = TextArea::from_text(
+            "日本
+flag 🇳🇿",
+        );
+        assert_eq!(area.cursor(), (1, 6));
+        assert!(area.backspace());
+        assert_eq!(
+            area.text(),
+            "日本
+flag "
+        );
+
+        // A combining accent joins the letter before it
+        area.move_up();
+        area.line_start();
+        area.move_right();
+        for c in "e\u{301}".chars() {
+            area.insert_char(c);
+        }
+        assert_eq!(area.cursor(), (0, 2));
+        assert!(area.delete());
+        assert_eq!(area.lines()[0], "日e\u{301}");
+
+        area.insert_text("-1\n-2");
+        assert_eq!(area.text(), "日e\u{301}-1\n-2\nflag ");
+        assert_eq!(area.cursor(), (1, 2));
+    }
+}
@@ -0,0 +1,68 @@
+use super::graphemes::grapheme_count;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Grapheme cluster ranges of the words in `text`, using Unicode word boundaries.
+/// Punctuation such as `/`, `-` and `:` separates words, so paths and URLs
+/// can be crossed one component at a time.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut clusters_before = 0;
+    for segment in text.split_word_bounds() {
+        let len = grapheme_count(segment);
+        if segment.chars().any(|c| c.is_alphanumeric() || c == '_') {
+            spans.push((clusters_before, clusters_before + len));
+        }
+        clusters_before += len;
+    }
+    spans
+}
+
+/// Start of the word before the cursor, skipping any separators in between
+pub fn word_start_before(text: &str, cursor: usize) -> usize {
+    word_spans(text)
+        .into_iter()
+        .rev()
+        .find(|&(start, _)| start < cursor)
+        .map_or(0, |(start, _)| start)
+}
+
+/// End of the word after the cursor, skipping any separators in between
+pub fn word_end_after(text: &str, cursor: usize) -> usize {
+    word_spans(text)
+        .into_iter()
+        .find(|&(_, end)| end > cursor)
+        .map_or_else(|| grapheme_count(text), |(_, end)| end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_boundaries_in_paths() {
+        let path = "/usr/local/bin/";
+        assert_eq!(word_start_before(path, 15), 11);
+        assert_eq!(word_start_before(path, 11), 5);
+        assert_eq!(word_start_before(path, 1), 0);
+        assert_eq!(word_end_after(path, 0), 4);
+        assert_eq!(word_end_after(path, 14), 15);
+    }
+
+    #[test]
+    fn test_word_boundaries_in_text() {
+        let url = "https://example.com/a-b";
+        // "example.com" is one word; ":" and "-" separate words
+        assert_eq!(word_end_after(url, 5), 19);
+        assert_eq!(word_start_before(url, 19), 8);
+        assert_eq!(word_start_before(url, 23), 22);
+
+        let text = "héllo  wörld";
+        assert_eq!(word_start_before(text, 12), 7);
+        assert_eq!(word_end_after(text, 5), 12);
+
+        // Positions count clusters, so a decomposed accent is one step
+        let text = "he\u{301}llo 日本";
+        assert_eq!(word_end_after(text, 0), 5);
+        assert_eq!(word_start_before(text, 6), 0);
+    }
+}
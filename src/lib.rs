@@ -14,6 +14,7 @@ pub use validation::rules::basic::{
     MaxLengthValidator, MinLengthValidator, PatternValidator, RequiredValidator,
 };
 pub use validation::rules::choice::ChoiceValidator;
+pub use validation::rules::command::ExternalCommandValidator;
 pub use validation::rules::datetime::{DateTimeValidator, DateValidator, TimeValidator};
 pub use validation::rules::filesystem::{
     DirExistsValidator, ExecutableValidator, FileExistsValidator, PathExistsValidator,
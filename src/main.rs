@@ -7,19 +7,25 @@ mod validation;
 
 use clap::{CommandFactory, Parser};
 use clap_complete::{generate, Shell as CompletionShell};
-use cli::{Args, Commands, PromptConfig, Shell};
+use cli::{load_form_spec, Args, Commands, FormOutputFormat, FormSpec, PromptConfig, Shell};
+use crossterm::tty::IsTty;
 use error::{PromptError, Result};
-use output::{DefaultFormatter, JsonFormatter, OutputFormatter, RawFormatter};
+use output::{format_form_answers, DefaultFormatter, JsonFormatter, OutputFormatter, RawFormatter};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
 use ui::interactive::InteractivePrompt;
-use ui::Terminal;
+use ui::terminal::{tty, UIMode};
+use ui::{ChoiceMenu, Terminal};
 use validation::rules::{
-    ChoiceValidator, DateTimeValidator, DateValidator, DirExistsValidator, EmailValidator,
-    ExecutableValidator, FileExistsValidator, FloatValidator, HostnameValidator, IntegerValidator,
-    Ipv4Validator, Ipv6Validator, MaxLengthValidator, MinLengthValidator, NegativeValidator,
-    PathExistsValidator, PatternValidator, PositiveValidator, RangeValidator, ReadableValidator,
-    RequiredValidator, TimeValidator, UrlValidator, WritableValidator,
+    ChoiceGroup, ChoiceValidator, DateTimeValidator, DateValidator, DirExistsValidator,
+    EmailValidator, ExecutableValidator, ExternalCommandValidator, FileExistsValidator,
+    FloatValidator, HostnameValidator, IntegerValidator, Ipv4Validator, Ipv6Validator,
+    MaxLengthValidator, MinLengthValidator, NegativeValidator, PathExistsValidator,
+    PatternValidator, PositiveValidator, RangeValidator, ReadableValidator, RequiredValidator,
+    TimeValidator, UnresolvedRuleValidator, UrlValidator, WritableValidator,
 };
-use validation::{ValidationEngine, ValidatorType};
+use validation::{ChoiceOption, ValidationEngine, ValidatorType};
 
 fn main() {
     if let Err(e) = run() {
@@ -31,35 +37,51 @@ fn main() {
 fn run() -> Result<()> {
     let args = Args::parse();
 
-    // Handle completion subcommand
-    if let Some(Commands::Completion { shell }) = args.command {
-        generate_completion(shell);
-        return Ok(());
+    match args.command {
+        // Handle completion subcommand
+        Some(Commands::Completion { shell }) => {
+            generate_completion(shell);
+            return Ok(());
+        }
+        // Handle multi-field forms
+        Some(Commands::Form { spec, output }) => {
+            let config = PromptConfig::from_args(args.prompt_args)?;
+            return run_form(&spec, &output, &config);
+        }
+        None => {}
     }
 
     let config = PromptConfig::from_args(args.prompt_args)?;
+    let engine = build_validation_engine(&config)?;
 
     // Get input based on mode
-    let input = if config.quiet_mode {
-        read_from_stdin()?
+    let (input, engine) = if config.quiet_mode {
+        (read_from_stdin()?, engine)
     } else {
         // Check if we can use interactive mode
         let terminal = Terminal::new()?;
 
-        if terminal.capabilities().cursor_control {
+        if terminal.capabilities().fallback_ui() != UIMode::Simple && !answer_on_stdin(&config) {
             // Use interactive terminal UI
-            let engine = build_validation_engine(&config)?;
             let mut interactive = InteractivePrompt::new(terminal, engine, config.clone())?;
-            interactive.prompt()?
+            let input = interactive.prompt()?;
+            (input, interactive.into_validation_engine())
         } else {
-            // Fall back to simple prompt
-            prompt_simple(&config.prompt_text.as_deref().unwrap_or("Enter input:"))?
+            // Fall back to simple prompt; multi-line text runs to the end of input
+            let prompt_text = config.prompt_text.as_deref().unwrap_or("Enter input:");
+            let input = if config.interaction_config.multiline {
+                eprintln!("{}", prompt_text);
+                read_from_stdin()?
+            } else {
+                prompt_simple(prompt_text)?
+            };
+            (input, engine)
         }
     };
 
-    // Final validation for output
-    let engine = build_validation_engine(&config)?;
-    let summary = engine.validate(&input);
+    // Final validation for output (reuses the engine so results cached on
+    // submit are not recomputed, e.g. external commands are not re-run)
+    let summary = engine.try_validate(&input)?;
 
     // Format output based on config
     let formatter: Box<dyn OutputFormatter> = match config.output_format {
@@ -87,12 +109,256 @@ fn run() -> Result<()> {
     }
 }
 
+fn run_form(
+    spec_path: &Path,
+    output_format: &FormOutputFormat,
+    base_config: &PromptConfig,
+) -> Result<()> {
+    let spec = load_form_spec(spec_path)?;
+
+    let answers = if base_config.quiet_mode {
+        // One answer per line on stdin
+        let mut lines = std::io::stdin().lines();
+        collect_form_answers_simple(&spec, base_config, |_| {
+            Ok(lines
+                .next()
+                .transpose()?
+                .unwrap_or_default()
+                .trim()
+                .to_string())
+        })?
+    } else if Terminal::new()?.capabilities().fallback_ui() != UIMode::Simple
+        && !answer_on_stdin(base_config)
+    {
+        collect_form_answers_interactive(&spec, base_config)?
+    } else {
+        collect_form_answers_simple(&spec, base_config, |config| {
+            prompt_simple(config.prompt_text.as_deref().unwrap_or_default())
+        })?
+    };
+
+    println!("{}", format_form_answers(&answers, output_format)?);
+    Ok(())
+}
+
+/// Collect form answers without a terminal UI; the first invalid answer is fatal
+fn collect_form_answers_simple(
+    spec: &FormSpec,
+    base_config: &PromptConfig,
+    mut read_answer: impl FnMut(&PromptConfig) -> Result<String>,
+) -> Result<Vec<(String, String)>> {
+    let mut answers: Vec<Option<String>> = vec![None; spec.fields.len()];
+
+    for (index, field) in spec.fields.iter().enumerate() {
+        if !spec.is_active(index, &answers) {
+            continue;
+        }
+
+        let config = spec.prompt_config(index, &answers, base_config);
+        let mut value = read_answer(&config)?;
+        if value.is_empty() {
+            if let Some(default) = &config.interaction_config.default_value {
+                value = default.clone();
+            }
+        }
+
+        let summary = build_validation_engine(&config)?.try_validate(&value)?;
+        if !summary.valid {
+            return Err(PromptError::ValidationFailed(format!(
+                "{}: {}",
+                field.name,
+                summary
+                    .error
+                    .unwrap_or_else(|| "Validation failed".to_string())
+            )));
+        }
+
+        answers[index] = Some(value);
+    }
+
+    Ok(spec.collected_answers(&answers))
+}
+
+/// Run the interactive form wizard: prompt for each field in turn, allowing
+/// Shift+Tab to revisit the previous field, then show a review screen
+fn collect_form_answers_interactive(
+    spec: &FormSpec,
+    base_config: &PromptConfig,
+) -> Result<Vec<(String, String)>> {
+    let mut answers: Vec<Option<String>> = vec![None; spec.fields.len()];
+    let mut index = 0;
+    let mut going_back = false;
+    let mut reviewing = false;
+
+    writeln!(tty(), "Press Shift+Tab to return to the previous field.")?;
+
+    loop {
+        if index == spec.fields.len() {
+            match review_form_answers(spec, &answers, base_config) {
+                Ok(Some(edit_index)) => {
+                    index = edit_index;
+                    reviewing = true;
+                    continue;
+                }
+                Ok(None) => {
+                    // Earlier edits may have invalidated answers that refer to them
+                    match first_invalid_answer(spec, &answers, base_config)? {
+                        Some((invalid_index, error)) => {
+                            writeln!(tty(), "Error: {}", error)?;
+                            index = invalid_index;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+                Err(PromptError::NavigateBack) => {
+                    index -= 1;
+                    going_back = true;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Skip fields whose condition is not met, in the direction of travel
+        if !spec.is_active(index, &answers) {
+            if going_back {
+                index -= 1;
+            } else {
+                index += 1;
+            }
+            continue;
+        }
+        going_back = false;
+
+        let mut config = spec.prompt_config(index, &answers, base_config);
+        if let Some(previous) = &answers[index] {
+            config.interaction_config.default_value = Some(previous.clone());
+        }
+        config.interaction_config.allow_back = index > 0;
+
+        let engine = build_validation_engine(&config)?;
+        let mut prompt = InteractivePrompt::new(Terminal::new()?, engine, config)?;
+        let result = prompt.prompt();
+        let engine = prompt.into_validation_engine();
+        writeln!(tty())?;
+
+        match result {
+            Ok(value) => {
+                // Choice menus only enforce selection counts, so validate fully here
+                let summary = engine.try_validate(&value)?;
+                if !summary.valid {
+                    if let Some(error) = summary.error {
+                        writeln!(tty(), "Error: {}", error)?;
+                    }
+                    continue;
+                }
+
+                answers[index] = Some(value);
+                index = if reviewing {
+                    // After editing from the review screen, only visit unanswered fields
+                    (0..spec.fields.len())
+                        .find(|&i| answers[i].is_none() && spec.is_active(i, &answers))
+                        .unwrap_or(spec.fields.len())
+                } else {
+                    index + 1
+                };
+            }
+            Err(PromptError::NavigateBack) => {
+                index -= 1;
+                going_back = true;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(spec.collected_answers(&answers))
+}
+
+/// Find the first asked field whose answer no longer passes its rules
+fn first_invalid_answer(
+    spec: &FormSpec,
+    answers: &[Option<String>],
+    base_config: &PromptConfig,
+) -> Result<Option<(usize, String)>> {
+    for (index, answer) in answers.iter().enumerate() {
+        if !spec.is_active(index, answers) {
+            continue;
+        }
+
+        let config = spec.prompt_config(index, answers, base_config);
+        let value = answer.as_deref().unwrap_or_default();
+        let summary = build_validation_engine(&config)?.try_validate(value)?;
+        if !summary.valid {
+            let error = summary
+                .error
+                .unwrap_or_else(|| "Validation failed".to_string());
+            return Ok(Some((
+                index,
+                format!("{}: {}", spec.fields[index].name, error),
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Show the collected answers; returns the index of a field to edit, or None to submit
+fn review_form_answers(
+    spec: &FormSpec,
+    answers: &[Option<String>],
+    base_config: &PromptConfig,
+) -> Result<Option<usize>> {
+    // Fields skipped by their conditions are not listed
+    let field_indices: Vec<usize> = (0..spec.fields.len())
+        .filter(|&i| spec.is_active(i, answers))
+        .collect();
+
+    let mut choices = vec!["Submit".to_string()];
+    for (n, &i) in field_indices.iter().enumerate() {
+        let field = &spec.fields[i];
+        let value = match &answers[i] {
+            Some(_) if field.mask => "********".to_string(),
+            Some(value) => value.clone(),
+            None => String::new(),
+        };
+        choices.push(format!("Edit {}. {}: {}", n + 1, field.name, value));
+    }
+
+    let timeout = base_config
+        .interaction_config
+        .timeout
+        .unwrap_or(Duration::from_secs(300));
+    let mut menu = ChoiceMenu::new(
+        Terminal::new()?,
+        choices.iter().cloned().map(ChoiceOption::new).collect(),
+        false,
+        1,
+        1,
+        base_config.ui_config.no_color,
+        timeout,
+        vec!["Submit".to_string()],
+    )?
+    .with_back_navigation(true)
+    .with_menu_height(base_config.ui_config.menu_height);
+    let selected = menu.show("Review your answers:");
+    drop(menu);
+    writeln!(tty())?;
+
+    let selected = selected?;
+    Ok(choices
+        .iter()
+        .position(|choice| selected.first() == Some(choice))
+        .filter(|&i| i > 0)
+        .map(|i| field_indices[i - 1]))
+}
+
 fn build_validation_engine(config: &PromptConfig) -> Result<ValidationEngine> {
     let mut engine = ValidationEngine::new();
 
     // Build validators from config
     for rule_config in &config.validation_rules {
-        let validator = create_validator(&rule_config.validator_type, &rule_config)?;
+        let validator = create_validator(&rule_config.validator_type, rule_config)?;
         engine.add_validator(validator);
     }
 
@@ -242,7 +508,8 @@ fn create_validator(
             Ok(Box::new(validator))
         }
         ValidatorType::Choices(choices) => {
-            let mut validator = ChoiceValidator::new(choices.clone());
+            let values = choices.iter().map(|choice| choice.value.clone()).collect();
+            let mut validator = ChoiceValidator::new(values);
 
             // Extract parameters
             if let Some(case_sensitive_str) = rule_config.parameters.get("case_sensitive") {
@@ -267,6 +534,25 @@ fn create_validator(
                 validator = validator.selection_separator(selection_separator);
             }
 
+            if let Some(suggestions_str) = rule_config.parameters.get("suggestions") {
+                if let Ok(suggestions) = suggestions_str.parse::<bool>() {
+                    validator = validator.with_suggestions(suggestions);
+                }
+            }
+
+            for choice in choices {
+                if let Some(reason) = &choice.disabled {
+                    validator = validator.with_disabled_choice(&choice.value, reason);
+                }
+                if choice.locked {
+                    validator = validator.with_locked_choice(&choice.value);
+                }
+            }
+
+            let groups = ChoiceGroup::from_choices(choices, &rule_config.parameters)
+                .map_err(PromptError::InvalidArguments)?;
+            validator = validator.with_groups(groups);
+
             if let Some(priority) = &rule_config.priority {
                 validator = validator.with_priority(*priority);
             }
@@ -365,9 +651,34 @@ fn create_validator(
             }
             Ok(Box::new(validator))
         }
+        ValidatorType::Unresolved(message) => Ok(Box::new(UnresolvedRuleValidator::new(message))),
+        ValidatorType::Command(command) => {
+            let mut validator = ExternalCommandValidator::new(command.clone());
+
+            if let Some(timeout_str) = rule_config.parameters.get("timeout") {
+                if let Ok(timeout) = timeout_str.parse::<u64>() {
+                    validator = validator.with_timeout(std::time::Duration::from_secs(timeout));
+                }
+            }
+
+            if let Some(priority) = &rule_config.priority {
+                validator = validator.with_priority(*priority);
+            }
+            if let Some(msg) = &rule_config.custom_message {
+                validator = validator.with_message(msg);
+            }
+            Ok(Box::new(validator))
+        }
     }
 }
 
+/// Piped stdin carries the answer, as in `echo yes | askr`, and is read
+/// without a UI. Choices piped in for `--choices-file -` are not an answer,
+/// so those prompts still run on the terminal.
+fn answer_on_stdin(config: &PromptConfig) -> bool {
+    !std::io::stdin().is_tty() && !config.interaction_config.stdin_choices
+}
+
 fn read_from_stdin() -> Result<String> {
     use std::io::{self, Read};
     let mut input = String::new();
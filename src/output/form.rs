@@ -0,0 +1,80 @@
+use crate::cli::args::FormOutputFormat;
+use crate::error::Result;
+
+/// Format the answers collected by a form, in field order
+pub fn format_form_answers(
+    answers: &[(String, String)],
+    format: &FormOutputFormat,
+) -> Result<String> {
+    match format {
+        FormOutputFormat::Json => {
+            let object: serde_json::Map<String, serde_json::Value> = answers
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                .collect();
+            Ok(serde_json::to_string_pretty(&object)?)
+        }
+        FormOutputFormat::Env => Ok(answers
+            .iter()
+            .map(|(name, value)| format!("{}={}", env_name(name), shell_quote(value)))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
+/// Turn a field name into a shell variable name (e.g. `api-key` -> `API_KEY`)
+pub(crate) fn env_name(name: &str) -> String {
+    let mut env_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if env_name.starts_with(|c: char| c.is_ascii_digit()) {
+        env_name.insert(0, '_');
+    }
+    env_name
+}
+
+/// Single-quote a value so it is safe to `eval` in a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers() -> Vec<(String, String)> {
+        vec![
+            ("username".to_string(), "alice".to_string()),
+            ("api-key".to_string(), "it's secret".to_string()),
+            ("2fa".to_string(), "yes".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_json_preserves_field_order() {
+        let output = format_form_answers(&answers(), &FormOutputFormat::Json).unwrap();
+
+        let username = output.find("username").unwrap();
+        let api_key = output.find("api-key").unwrap();
+        assert!(username < api_key);
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["api-key"], "it's secret");
+    }
+
+    #[test]
+    fn test_env_assignments() {
+        let output = format_form_answers(&answers(), &FormOutputFormat::Env).unwrap();
+
+        assert_eq!(
+            output,
+            "USERNAME='alice'\nAPI_KEY='it'\\''s secret'\n_2FA='yes'"
+        );
+    }
+}
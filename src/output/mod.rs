@@ -1,8 +1,10 @@
 pub mod default;
+pub mod form;
 pub mod json;
 pub mod raw;
 
 pub use default::*;
+pub use form::*;
 pub use json::*;
 pub use raw::*;
 
@@ -1,12 +1,19 @@
-use super::{ColorScheme, Colorizer, LayoutManager, Screen, Terminal};
+use super::colors::ColoredText;
+use super::terminal::{tty, Tty};
+use super::{Colorizer, LayoutManager, Screen, Terminal};
 use crate::error::{PromptError, Result};
+use crate::validation::rules::ChoiceGroup;
+use crate::validation::ChoiceOption;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
-use std::io::{self, stderr};
 use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Shown in place of the choices when the filter matches nothing
+const NO_MATCHES: &str = "  No matching choices";
 
 pub struct ChoiceMenu {
     terminal: Terminal,
-    choices: Vec<String>,
+    choices: Vec<ChoiceOption>,
     allow_multiple: bool,
     min_choices: usize,
     max_choices: usize,
@@ -14,16 +21,22 @@ pub struct ChoiceMenu {
     current_index: usize,
     colorizer: Colorizer,
     validation_error: Option<String>,
-    last_content_lines: u16, // Track how many content lines were drawn last time
+    drawn_lines: Vec<usize>, // Width of each line of the last draw, from the prompt down
     timeout: Duration,
-    has_defaults: bool,        // Track if defaults were provided
-    user_has_interacted: bool, // Track if user has made any selections/deselections
+    default_selections: Vec<String>, // Preselected values, in the order given
+    user_has_interacted: bool,       // Track if user has made any selections/deselections
+    allow_back: bool,                // Shift+Tab returns to the previous form field
+    filter: String,                  // Typed text narrowing the visible choices
+    menu_height: Option<u16>,        // Maximum number of choice rows shown at once
+    scroll_offset: usize,            // First visible row shown in the viewport
+    groups: Vec<ChoiceGroup>,        // Per-group selection limits shown in headers
+    ranked: bool,                    // The list order is a ranking the user can change
 }
 
 impl ChoiceMenu {
     pub fn new(
         mut terminal: Terminal,
-        choices: Vec<String>,
+        choices: Vec<ChoiceOption>,
         allow_multiple: bool,
         min_choices: usize,
         max_choices: usize,
@@ -36,22 +49,42 @@ impl ChoiceMenu {
             terminal.enter_raw_mode()?;
         }
 
-        let color_scheme = if no_color {
-            ColorScheme::no_color()
-        } else {
-            ColorScheme::default()
-        };
+        let colorizer = Colorizer::for_terminal(terminal.capabilities(), no_color);
+
+        // List each group's choices together, in order of the group's first
+        // appearance, so every group gets a single header
+        let mut group_order: Vec<Option<String>> = Vec::new();
+        for choice in &choices {
+            if choice.group.is_some() && !group_order.contains(&choice.group) {
+                group_order.push(choice.group.clone());
+            }
+        }
+        let mut choices = choices;
+        choices.sort_by_key(|choice| {
+            group_order
+                .iter()
+                .position(|group| group == &choice.group)
+                .map_or(0, |position| position + 1)
+        });
 
-        let colorizer = Colorizer::new(color_scheme, no_color);
         let mut selected_choices = vec![false; choices.len()];
-        let has_defaults = !default_selections.is_empty();
 
-        // Preselect default choices
+        // Preselect default choices, skipping any that are disabled
         for default_choice in &default_selections {
-            if let Some(index) = choices.iter().position(|choice| choice == default_choice) {
+            if let Some(index) = choices
+                .iter()
+                .position(|choice| &choice.value == default_choice && choice.disabled.is_none())
+            {
                 selected_choices[index] = true;
             }
         }
+        for (selected, choice) in selected_choices.iter_mut().zip(&choices) {
+            *selected |= choice.locked;
+        }
+        let current_index = choices
+            .iter()
+            .position(|choice| choice.disabled.is_none())
+            .unwrap_or(0);
 
         Ok(Self {
             terminal,
@@ -60,26 +93,83 @@ impl ChoiceMenu {
             min_choices,
             max_choices,
             selected_choices,
-            current_index: 0,
+            current_index,
             colorizer,
             validation_error: None,
-            last_content_lines: 0,
+            drawn_lines: Vec::new(),
             timeout,
-            has_defaults,
+            default_selections,
             user_has_interacted: false,
+            allow_back: false,
+            filter: String::new(),
+            menu_height: None,
+            scroll_offset: 0,
+            groups: Vec::new(),
+            ranked: false,
         })
     }
 
+    pub fn with_back_navigation(mut self, allow_back: bool) -> Self {
+        self.allow_back = allow_back;
+        self
+    }
+
+    /// Limit the number of choices shown at once; longer lists scroll
+    pub fn with_menu_height(mut self, menu_height: Option<u16>) -> Self {
+        self.menu_height = menu_height;
+        self
+    }
+
+    /// Enforce per-group selection limits and show them in group headers
+    pub fn with_groups(mut self, groups: Vec<ChoiceGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Let the user rank the choices: SHIFT+↑↓ moves the current choice,
+    /// newly selected choices move below the ones already selected, and
+    /// selections are returned in list order
+    pub fn with_ranking(mut self, ranked: bool) -> Self {
+        self.ranked = ranked;
+        if ranked {
+            // Preselected choices start out ranked in the order they were given
+            let defaults: Vec<usize> = self
+                .default_selections
+                .iter()
+                .filter_map(|value| self.choices.iter().position(|c| &c.value == value))
+                .filter(|&i| self.selected_choices[i] && !self.choices[i].locked)
+                .collect();
+            for &i in &defaults {
+                self.selected_choices[i] = false;
+            }
+            let values: Vec<String> = defaults
+                .iter()
+                .map(|&i| self.choices[i].value.clone())
+                .collect();
+            for value in values {
+                if let Some(index) = self.choices.iter().position(|c| c.value == value) {
+                    self.selected_choices[index] = true;
+                    self.rank_after_selected(index);
+                }
+            }
+            self.current_index = self
+                .choices
+                .iter()
+                .position(|choice| choice.disabled.is_none())
+                .unwrap_or(0);
+        }
+        self
+    }
+
     pub fn show(&mut self, prompt_text: &str) -> Result<Vec<String>> {
         use crossterm::{terminal::Clear, terminal::ClearType, ExecutableCommand};
-        use std::io::stderr;
 
         // Clear any existing content on the current line first
-        stderr().execute(Clear(ClearType::CurrentLine))?;
+        tty().execute(Clear(ClearType::CurrentLine))?;
 
         let (width, height) = self.terminal.size()?;
         let layout = LayoutManager::new(width, height);
-        let mut screen = Screen::new(stderr(), layout, self.colorizer.clone());
+        let mut screen = Screen::new(tty(), layout, self.colorizer.clone());
 
         // Calculate space needed and reserve it
         let reserved_lines = self.calculate_and_reserve_space(width, prompt_text)?;
@@ -89,36 +179,51 @@ impl ChoiceMenu {
 
         // Validate initial state and draw menu
         self.validate_selections();
-        self.draw_menu(&mut screen, prompt_text)?;
-        self.update_content_line_count();
+        self.scroll_to_current();
+        self.drawn_lines = self.draw_menu(&mut screen, prompt_text)?;
 
         // Input loop
         loop {
             screen.flush()?;
 
             if event::poll(self.timeout)? {
-                if let Event::Key(key_event) = event::read()? {
-                    match self.handle_key_event(key_event)? {
-                        MenuAction::Continue => {
-                            // Validate selections after any change
+                let action = match event::read()? {
+                    Event::Key(key_event) => self.handle_key_event(key_event)?,
+                    // The menu is redrawn below for the new size
+                    Event::Resize(..) => MenuAction::Continue,
+                    // Pasted text goes to the filter, up to the first line break
+                    Event::Paste(text) => {
+                        let line = text.split(['\r', '\n']).next().unwrap_or_default();
+                        self.filter.push_str(line);
+                        self.user_has_interacted = true;
+                        self.apply_filter();
+                        MenuAction::Continue
+                    }
+                    _ => continue,
+                };
+                match action {
+                    MenuAction::Continue => {
+                        // Validate selections after any change
+                        self.validate_selections();
+                        // Clear the area and redraw
+                        self.clear_and_redraw(&mut screen, prompt_text)?;
+                    }
+                    MenuAction::Submit => {
+                        // Only submit if constraints are met
+                        if self.can_submit() {
+                            let selected = self.get_selected_choices();
+                            return Ok(selected);
+                        } else {
+                            // Validation failed, update error and redraw
                             self.validate_selections();
-                            // Clear the area and redraw
                             self.clear_and_redraw(&mut screen, prompt_text)?;
                         }
-                        MenuAction::Submit => {
-                            // Only submit if constraints are met
-                            if self.can_submit() {
-                                let selected = self.get_selected_choices();
-                                return Ok(selected);
-                            } else {
-                                // Validation failed, update error and redraw
-                                self.validate_selections();
-                                self.clear_and_redraw(&mut screen, prompt_text)?;
-                            }
-                        }
-                        MenuAction::Cancel => {
-                            return Err(PromptError::Interrupted);
-                        }
+                    }
+                    MenuAction::Cancel => {
+                        return Err(PromptError::Interrupted);
+                    }
+                    MenuAction::Back => {
+                        return Err(PromptError::NavigateBack);
                     }
                 }
             } else {
@@ -129,24 +234,65 @@ impl ChoiceMenu {
 
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<MenuAction> {
         match key_event {
+            // Reordering in ranking mode
+            KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            } if self.ranked => {
+                self.move_current_choice(-1);
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            } if self.ranked => {
+                self.move_current_choice(1);
+                Ok(MenuAction::Continue)
+            }
+
             // Navigation with arrow keys
             KeyEvent {
                 code: KeyCode::Up, ..
             } => {
-                if self.current_index > 0 {
-                    self.current_index -= 1;
-                    self.user_has_interacted = true;
-                }
+                self.move_cursor(-1);
                 Ok(MenuAction::Continue)
             }
             KeyEvent {
                 code: KeyCode::Down,
                 ..
             } => {
-                if self.current_index < self.choices.len() - 1 {
-                    self.current_index += 1;
-                    self.user_has_interacted = true;
-                }
+                self.move_cursor(1);
+                Ok(MenuAction::Continue)
+            }
+
+            // Paging through long lists
+            KeyEvent {
+                code: KeyCode::PageUp,
+                ..
+            } => {
+                self.move_cursor(-(self.viewport_height() as isize));
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::PageDown,
+                ..
+            } => {
+                self.move_cursor(self.viewport_height() as isize);
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::Home,
+                ..
+            } => {
+                self.move_cursor(-(self.choices.len() as isize));
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::End, ..
+            } => {
+                self.move_cursor(self.choices.len() as isize);
                 Ok(MenuAction::Continue)
             }
 
@@ -160,9 +306,12 @@ impl ChoiceMenu {
                     Ok(MenuAction::Submit)
                 } else {
                     // In single choice mode, check if we have defaults and user hasn't interacted
-                    if self.has_defaults && !self.user_has_interacted {
+                    if !self.default_selections.is_empty() && !self.user_has_interacted {
                         // Submit with defaults (already preselected)
                         Ok(MenuAction::Submit)
+                    } else if !self.is_selectable(self.current_index) {
+                        // Nothing selectable matches the filter
+                        Ok(MenuAction::Continue)
                     } else {
                         // Select current item and submit
                         self.selected_choices.fill(false);
@@ -176,15 +325,37 @@ impl ChoiceMenu {
             KeyEvent {
                 code: KeyCode::Char(' '),
                 ..
-            } => {
-                if self.allow_multiple {
+            } if self.allow_multiple => {
+                if self.is_selectable(self.current_index)
+                    && !self.choices[self.current_index].locked
+                {
                     self.selected_choices[self.current_index] =
                         !self.selected_choices[self.current_index];
+                    if self.ranked && self.selected_choices[self.current_index] {
+                        self.rank_after_selected(self.current_index);
+                    }
                     self.user_has_interacted = true;
                 }
                 Ok(MenuAction::Continue)
             }
 
+            // Select (or, if all are selected, deselect) the visible choices
+            // in the current choice's group
+            KeyEvent {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } if self.allow_multiple => {
+                self.toggle_current_group();
+                Ok(MenuAction::Continue)
+            }
+
+            // Return to the previous form field with Shift+Tab
+            KeyEvent {
+                code: KeyCode::BackTab,
+                ..
+            } if self.allow_back => Ok(MenuAction::Back),
+
             // Cancel with Ctrl+C
             KeyEvent {
                 code: KeyCode::Char('c'),
@@ -199,31 +370,57 @@ impl ChoiceMenu {
                 ..
             } => Ok(MenuAction::Cancel),
 
+            // Typing narrows the list; selections of hidden choices are kept
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+                ..
+            } if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.filter.push(c);
+                self.user_has_interacted = true;
+                self.apply_filter();
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => {
+                self.filter.pop();
+                self.apply_filter();
+                Ok(MenuAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => {
+                self.filter.clear();
+                self.apply_filter();
+                Ok(MenuAction::Continue)
+            }
+
             // Ignore other keys
             _ => Ok(MenuAction::Continue),
         }
     }
 
-    fn draw_menu(&self, screen: &mut Screen<io::Stderr>, prompt_text: &str) -> Result<()> {
+    /// Draw the prompt and the menu below it, returning the width of each
+    /// line drawn
+    fn draw_menu(&self, screen: &mut Screen<Tty>, prompt_text: &str) -> Result<Vec<usize>> {
         use crossterm::{cursor::MoveToNextLine, ExecutableCommand};
 
         // Draw prompt at current position (which should be the reserved position)
-        let _prompt_width = screen.write_prompt(prompt_text)?;
+        screen.write_prompt(prompt_text)?;
+        let mut lines = vec![UnicodeWidthStr::width(prompt_text) + 1];
 
         // Move to next line for instructions and choices
-        stderr().execute(MoveToNextLine(1))?;
+        tty().execute(MoveToNextLine(1))?;
 
         // Draw the menu content
-        self.draw_menu_content()?;
+        lines.extend(self.draw_menu_content()?);
 
-        Ok(())
+        Ok(lines)
     }
 
-    fn clear_and_redraw(
-        &mut self,
-        screen: &mut Screen<io::Stderr>,
-        prompt_text: &str,
-    ) -> Result<()> {
+    fn clear_and_redraw(&mut self, screen: &mut Screen<Tty>, prompt_text: &str) -> Result<()> {
         use crossterm::{
             cursor::{MoveToColumn, MoveUp},
             terminal::Clear,
@@ -231,101 +428,520 @@ impl ChoiceMenu {
             ExecutableCommand,
         };
 
-        // Move back using the tracked number of content lines from last draw
-        // Add 1 to account for the prompt line
-        if self.last_content_lines > 0 {
-            stderr().execute(MoveUp(self.last_content_lines + 1))?;
+        // Move back up to the prompt line, over the lines of the last draw
+        let width = self.terminal.size().map(|(w, _)| w).unwrap_or(80);
+        let rows = self.rows_above_cursor(width);
+        if rows > 0 {
+            tty().execute(MoveUp(rows))?;
         }
 
         // Move to the beginning of the line to ensure proper cursor positioning
-        stderr().execute(MoveToColumn(0))?;
+        tty().execute(MoveToColumn(0))?;
+
+        // Keep the cursor inside the viewport
+        self.scroll_to_current();
 
         // Clear from cursor down to remove old menu content
-        stderr().execute(Clear(ClearType::FromCursorDown))?;
+        tty().execute(Clear(ClearType::FromCursorDown))?;
 
         // Redraw the full menu (prompt + instruction + choices + error)
-        self.draw_menu(screen, prompt_text)?;
-
-        // Update the line count for next time
-        self.update_content_line_count();
+        self.drawn_lines = self.draw_menu(screen, prompt_text)?;
 
         Ok(())
     }
 
-    /// Calculate and store how many lines the current content occupies
-    fn update_content_line_count(&mut self) {
-        let mut lines = 1; // instruction line
-        lines += self.choices.len() as u16; // choice lines
-        if self.validation_error.is_some() {
-            lines += 1; // error line
-        }
-        // Note: prompt line is handled separately in clear_and_redraw
-        self.last_content_lines = lines;
+    /// Rows from the prompt line down to the cursor, which the last draw
+    /// left on its last line, with the drawn lines wrapped at `width`. When
+    /// the terminal has just been resized this follows terminals that
+    /// re-wrap long lines to the new width, as most do.
+    fn rows_above_cursor(&self, width: u16) -> u16 {
+        let width = width.max(1) as usize;
+        let Some((last, above)) = self.drawn_lines.split_last() else {
+            return 0;
+        };
+        let rows: usize = above.iter().map(|&w| w.max(1).div_ceil(width)).sum();
+        (rows + last.saturating_sub(1) / width) as u16
     }
 
-    fn draw_menu_content(&self) -> Result<()> {
-        use crossterm::{cursor::MoveToNextLine, ExecutableCommand};
-
-        // Add instruction text
-        let instruction = if self.allow_multiple {
+    fn instruction(&self) -> String {
+        if self.allow_multiple {
+            let toggle = if self.choices.iter().any(|choice| choice.group.is_some()) {
+                "SPACE to toggle, CTRL+A to toggle group"
+            } else {
+                "SPACE to toggle"
+            };
+            let arrows = self.colorizer.symbol("↑↓", "Up/Down");
+            let (order, navigate) = if self.ranked {
+                (
+                    " in order of preference",
+                    format!("{} to navigate, SHIFT+{} to reorder", arrows, arrows),
+                )
+            } else {
+                ("", format!("{} to navigate", arrows))
+            };
             if self.min_choices == self.max_choices {
-                format!("Select exactly {} choice(s). Use ↑↓ to navigate, SPACE to toggle, ENTER to submit:", self.min_choices)
+                format!(
+                    "Select exactly {} choice(s){}. Use {}, type to filter, {}, ENTER to submit:",
+                    self.min_choices, order, navigate, toggle
+                )
             } else {
                 format!(
-                    "Select {}-{} choice(s). Use ↑↓ to navigate, SPACE to toggle, ENTER to submit:",
-                    self.min_choices, self.max_choices
+                    "Select {}-{} choice(s){}. Use {}, type to filter, {}, ENTER to submit:",
+                    self.min_choices, self.max_choices, order, navigate, toggle
                 )
             }
         } else {
-            "Use ↑↓ to navigate, ENTER to select:".to_string()
-        };
+            format!(
+                "Use {} to navigate, type to filter, ENTER to select:",
+                self.colorizer.symbol("↑↓", "Up/Down")
+            )
+        }
+    }
 
-        // Write instruction
-        let colored_instruction = self.colorizer.help_text(&instruction);
-        self.colorizer
-            .write_colored(&mut std::io::stderr(), &colored_instruction)?;
-        stderr().execute(MoveToNextLine(1))?;
+    /// Describe the active filter and how much of the list it hides
+    fn filter_status(&self, visible: &[usize]) -> String {
+        let hidden_selected = self
+            .selected_choices
+            .iter()
+            .enumerate()
+            .filter(|&(i, &selected)| selected && !visible.contains(&i))
+            .count();
 
-        // Draw choices
-        for (i, choice) in self.choices.iter().enumerate() {
-            let is_current = i == self.current_index;
-            let is_selected = self.selected_choices[i];
+        let mut status = format!(
+            "Filter: {} ({} of {} shown",
+            self.filter,
+            visible.len(),
+            self.choices.len()
+        );
+        if hidden_selected > 0 {
+            status.push_str(&format!(", {} selected hidden", hidden_selected));
+        }
+        status.push_str("; ESC to clear)");
+        status
+    }
 
-            let marker = if self.allow_multiple {
-                if is_selected {
-                    "[✓]"
-                } else {
-                    "[ ]"
-                }
-            } else if is_current {
-                ">"
+    fn marker(&self, index: usize) -> String {
+        if self.allow_multiple {
+            if !self.selected_choices[index] {
+                "[ ]".to_string()
+            } else if self.ranked {
+                // Selected choices show their rank
+                let rank = self.selected_choices[..=index]
+                    .iter()
+                    .filter(|&&selected| selected)
+                    .count();
+                format!("[{}]", rank)
             } else {
-                " "
-            };
+                format!("[{}]", self.colorizer.symbol("✓", "x"))
+            }
+        } else if index == self.current_index {
+            ">".to_string()
+        } else {
+            " ".to_string()
+        }
+    }
 
-            let choice_text = format!("{} {}", marker, choice);
+    /// Draw the instruction, choices and any error, returning the width of
+    /// each line drawn. The cursor is left at the end of the last line.
+    fn draw_menu_content(&self) -> Result<Vec<usize>> {
+        use crossterm::{cursor::MoveToNextLine, ExecutableCommand};
+        let mut lines = Vec::new();
 
-            if is_current {
-                let colored_choice = self.colorizer.highlighted_text(&choice_text);
-                self.colorizer
-                    .write_colored(&mut std::io::stderr(), &colored_choice)?;
-            } else {
-                let colored_choice = self.colorizer.valid_text(&choice_text);
+        // Write instruction
+        let colored_instruction = self.colorizer.help_text(self.instruction());
+        self.colorizer
+            .write_colored(&mut tty(), &colored_instruction)?;
+        tty().execute(MoveToNextLine(1))?;
+        lines.push(UnicodeWidthStr::width(colored_instruction.text.as_str()));
+
+        let visible = self.visible_choices();
+
+        if !self.filter.is_empty() {
+            let indices: Vec<usize> = visible.iter().map(|(i, _)| *i).collect();
+            let status = self.colorizer.help_text(self.filter_status(&indices));
+            self.colorizer.write_colored(&mut tty(), &status)?;
+            tty().execute(MoveToNextLine(1))?;
+            lines.push(UnicodeWidthStr::width(status.text.as_str()));
+
+            if visible.is_empty() {
                 self.colorizer
-                    .write_colored(&mut std::io::stderr(), &colored_choice)?;
+                    .write_colored(&mut tty(), &self.colorizer.help_text(NO_MATCHES))?;
+                tty().execute(MoveToNextLine(1))?;
+                lines.push(UnicodeWidthStr::width(NO_MATCHES));
             }
-            stderr().execute(MoveToNextLine(1))?;
         }
 
-        // Display validation error if present
+        // Draw the rows inside the viewport, noting how many choices are scrolled out of view
+        let rows = self.menu_rows();
+        let (start, end) = self.viewport(rows.len());
+        let width = self.terminal.size().map(|(w, _)| w).unwrap_or(80) as usize;
+        let count_choices = |rows: &[MenuRow]| {
+            rows.iter()
+                .filter(|row| matches!(row, MenuRow::Choice(..)))
+                .count()
+        };
+        if start > 0 {
+            let above = format!(
+                "  {} {} more above",
+                self.colorizer.symbol("↑", "^"),
+                count_choices(&rows[..start])
+            );
+            lines.push(UnicodeWidthStr::width(above.as_str()));
+            self.colorizer
+                .write_colored(&mut tty(), &self.colorizer.help_text(above))?;
+            tty().execute(MoveToNextLine(1))?;
+        }
+        for row in &rows[start..end] {
+            let drawn = match row {
+                MenuRow::Header(group) => self.write_header(group, width)?,
+                MenuRow::Choice(i, matched) => {
+                    let marker = format!("{} ", self.marker(*i));
+                    self.write_choice(
+                        &marker,
+                        &self.choices[*i],
+                        matched,
+                        *i == self.current_index,
+                        width,
+                    )?
+                }
+            };
+            tty().execute(MoveToNextLine(1))?;
+            lines.push(drawn);
+        }
+        if end < rows.len() {
+            let below = format!(
+                "  {} {} more below",
+                self.colorizer.symbol("↓", "v"),
+                count_choices(&rows[end..])
+            );
+            lines.push(UnicodeWidthStr::width(below.as_str()));
+            self.colorizer
+                .write_colored(&mut tty(), &self.colorizer.help_text(below))?;
+            tty().execute(MoveToNextLine(1))?;
+        }
+        // The cursor is now at the start of an empty line
+        lines.push(0);
+
+        // Display validation error if present, after a blank line
         if let Some(error_message) = &self.validation_error {
-            stderr().execute(MoveToNextLine(1))?;
+            tty().execute(MoveToNextLine(1))?;
             let colored_error = self.colorizer.error_message(error_message);
+            self.colorizer.write_colored(&mut tty(), &colored_error)?;
+            lines.push(UnicodeWidthStr::width(colored_error.text.as_str()));
+        }
+
+        Ok(lines)
+    }
+
+    /// Write a group header: its name, then any selection limits dimmed
+    fn write_header(&self, group: &str, width: usize) -> Result<usize> {
+        let mut cells: Vec<(char, Emphasis)> =
+            group.chars().map(|c| (c, Emphasis::Normal)).collect();
+        let limits =
+            self.groups
+                .iter()
+                .find(|g| g.name == group)
+                .and_then(|g| match (g.min, g.max) {
+                    (Some(min), Some(max)) if min == max => Some(format!(" (select {})", min)),
+                    (Some(min), Some(max)) => Some(format!(" (select {}-{})", min, max)),
+                    (Some(min), None) => Some(format!(" (at least {})", min)),
+                    (None, Some(max)) => Some(format!(" (at most {})", max)),
+                    (None, None) => None,
+                });
+        if let Some(limits) = limits {
+            cells.extend(limits.chars().map(|c| (c, Emphasis::Dimmed)));
+        }
+
+        self.write_cells("", cells, width, |text, emphasis| match emphasis {
+            Emphasis::Dimmed => self.colorizer.help_text(text),
+            _ => self.colorizer.prompt_text(text).bold(),
+        })
+    }
+
+    /// Write a choice line: its label, emphasising the characters that matched
+    /// the filter, then any description and disabled reason or locked note
+    /// dimmed. Disabled choices are greyed out entirely. Lines wider than the
+    /// terminal are cut short with an ellipsis.
+    fn write_choice(
+        &self,
+        marker: &str,
+        choice: &ChoiceOption,
+        matched: &[usize],
+        is_current: bool,
+        width: usize,
+    ) -> Result<usize> {
+        let disabled = choice.disabled.is_some();
+        let style = |text: String, emphasis: Emphasis| match (is_current, emphasis) {
+            _ if disabled => self.colorizer.help_text(text),
+            (true, Emphasis::Matched) => self.colorizer.highlighted_text(text).underlined(),
+            (true, _) => self.colorizer.highlighted_text(text),
+            (false, Emphasis::Normal) => self.colorizer.valid_text(text),
+            (false, Emphasis::Matched) => self.colorizer.matched_text(text),
+            (false, Emphasis::Dimmed) => self.colorizer.help_text(text),
+        };
+
+        let mut cells: Vec<(char, Emphasis)> = choice
+            .display_label()
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let emphasis = if matched.contains(&i) {
+                    Emphasis::Matched
+                } else {
+                    Emphasis::Normal
+                };
+                (c, emphasis)
+            })
+            .collect();
+        if let Some(description) = &choice.description {
+            let description = format!(" ({})", description);
+            cells.extend(description.chars().map(|c| (c, Emphasis::Dimmed)));
+        }
+        let state = match &choice.disabled {
+            Some(reason) if !reason.is_empty() => Some(format!(" ({})", reason)),
+            Some(_) => Some(" (unavailable)".to_string()),
+            None if choice.locked => Some(" (locked)".to_string()),
+            None => None,
+        };
+        if let Some(state) = state {
+            cells.extend(state.chars().map(|c| (c, Emphasis::Dimmed)));
+        }
+
+        self.write_cells(marker, cells, width, style)
+    }
+
+    /// Write a marker and styled characters, cutting lines wider than the
+    /// terminal short with an ellipsis. Returns the columns written.
+    fn write_cells(
+        &self,
+        marker: &str,
+        cells: Vec<(char, Emphasis)>,
+        width: usize,
+        style: impl Fn(String, Emphasis) -> ColoredText,
+    ) -> Result<usize> {
+        let mut out = tty();
+        self.colorizer
+            .write_colored(&mut out, &style(marker.to_string(), Emphasis::Normal))?;
+
+        // Leave the last column free so the line never wraps
+        let available = width.saturating_sub(UnicodeWidthStr::width(marker) + 1);
+        let total: usize = cells
+            .iter()
+            .map(|(c, _)| UnicodeWidthChar::width(*c).unwrap_or(0))
+            .sum();
+        let truncated = total > available;
+        let mut used = 0;
+
+        // Group consecutive characters with the same emphasis into runs
+        let mut run = String::new();
+        let mut run_emphasis = Emphasis::Normal;
+        for (c, emphasis) in cells {
+            let char_width = UnicodeWidthChar::width(c).unwrap_or(0);
+            if truncated && used + char_width + 1 > available {
+                break;
+            }
+            used += char_width;
+
+            if emphasis != run_emphasis && !run.is_empty() {
+                self.colorizer
+                    .write_colored(&mut out, &style(std::mem::take(&mut run), run_emphasis))?;
+            }
+            run_emphasis = emphasis;
+            run.push(c);
+        }
+        if !run.is_empty() {
             self.colorizer
-                .write_colored(&mut std::io::stderr(), &colored_error)?;
+                .write_colored(&mut out, &style(run, run_emphasis))?;
+        }
+        if truncated {
+            self.colorizer.write_colored(
+                &mut out,
+                &style(self.colorizer.symbol("…", ">").to_string(), run_emphasis),
+            )?;
         }
 
-        Ok(())
+        Ok(UnicodeWidthStr::width(marker) + used + usize::from(truncated))
+    }
+
+    /// Choices matching the current filter, with the positions of matched characters
+    fn visible_choices(&self) -> Vec<(usize, Vec<usize>)> {
+        self.choices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, choice)| Some((i, choice_match(choice, &self.filter)?)))
+            .collect()
+    }
+
+    /// The rows of the choice list: each visible choice, preceded by a header
+    /// where a new group starts
+    fn menu_rows(&self) -> Vec<MenuRow> {
+        let mut rows = Vec::new();
+        let mut current_group = None;
+        for (i, matched) in self.visible_choices() {
+            let group = self.choices[i].group.as_deref();
+            if group != current_group {
+                if let Some(name) = group {
+                    rows.push(MenuRow::Header(name.to_string()));
+                }
+                current_group = group;
+            }
+            rows.push(MenuRow::Choice(i, matched));
+        }
+        rows
+    }
+
+    fn visible_indices(&self) -> Vec<usize> {
+        self.visible_choices().into_iter().map(|(i, _)| i).collect()
+    }
+
+    /// Visible choices the cursor can land on; disabled choices are skipped
+    fn selectable_indices(&self) -> Vec<usize> {
+        self.visible_indices()
+            .into_iter()
+            .filter(|&i| self.choices[i].disabled.is_none())
+            .collect()
+    }
+
+    fn is_selectable(&self, index: usize) -> bool {
+        self.choices[index].disabled.is_none()
+            && choice_match(&self.choices[index], &self.filter).is_some()
+    }
+
+    /// Move the cursor by `delta` selectable choices, stopping at either end
+    fn move_cursor(&mut self, delta: isize) {
+        let visible = self.selectable_indices();
+        if let Some(pos) = visible.iter().position(|&i| i == self.current_index) {
+            let target = (pos as isize)
+                .saturating_add(delta)
+                .clamp(0, visible.len() as isize - 1) as usize;
+            if target != pos {
+                self.current_index = visible[target];
+                self.user_has_interacted = true;
+            }
+        }
+    }
+
+    /// Swap the current choice with its visible neighbour `delta` (±1) rows
+    /// away, staying within its group; the cursor follows the choice
+    fn move_current_choice(&mut self, delta: isize) {
+        let visible = self.visible_indices();
+        let Some(pos) = visible.iter().position(|&i| i == self.current_index) else {
+            return;
+        };
+        let Some(&neighbour) = pos
+            .checked_add_signed(delta)
+            .and_then(|target| visible.get(target))
+        else {
+            return;
+        };
+        if self.choices[neighbour].group != self.choices[self.current_index].group {
+            return;
+        }
+        self.choices.swap(self.current_index, neighbour);
+        self.selected_choices.swap(self.current_index, neighbour);
+        self.current_index = neighbour;
+        self.user_has_interacted = true;
+    }
+
+    /// Move a newly selected choice to just below the other selected choices
+    /// of its group, so picking choices one by one builds the ranking
+    fn rank_after_selected(&mut self, index: usize) {
+        let group = &self.choices[index].group;
+        let in_group = |i: &usize| &self.choices[*i].group == group;
+        let last_selected = (0..self.choices.len())
+            .rev()
+            .filter(in_group)
+            .find(|&i| i != index && self.selected_choices[i]);
+        let target = match last_selected {
+            Some(last) if last < index => last + 1,
+            Some(last) => last,
+            // The first selection in the group moves to its top
+            None => (0..self.choices.len()).find(in_group).unwrap_or(index),
+        };
+
+        let choice = self.choices.remove(index);
+        let selected = self.selected_choices.remove(index);
+        self.choices.insert(target, choice);
+        self.selected_choices.insert(target, selected);
+        self.current_index = target;
+    }
+
+    /// Select every visible choice in the current choice's group, or deselect
+    /// them all if they are already selected. Disabled and locked choices
+    /// keep their state.
+    fn toggle_current_group(&mut self) {
+        if !self.is_selectable(self.current_index) {
+            return;
+        }
+        let group = self.choices[self.current_index].group.clone();
+        let members: Vec<usize> = self
+            .selectable_indices()
+            .into_iter()
+            .filter(|&i| self.choices[i].group == group && !self.choices[i].locked)
+            .collect();
+        let select = !members.iter().all(|&i| self.selected_choices[i]);
+        for i in members {
+            self.selected_choices[i] = select;
+        }
+        self.user_has_interacted = true;
+    }
+
+    /// Lines used by everything except the choices: prompt, instruction,
+    /// filter status, scroll indicators, and the blank and error lines
+    fn chrome_lines(&self) -> usize {
+        let width = self.terminal.size().map(|(w, _)| w).unwrap_or(80).max(1) as usize;
+        let instruction_rows = UnicodeWidthStr::width(self.instruction().as_str()).div_ceil(width);
+        1 + instruction_rows + 1 + 2 + 2
+    }
+
+    /// Number of rows (choices and group headers) shown at once
+    fn viewport_height(&self) -> usize {
+        let (_, height) = self.terminal.size().unwrap_or((80, 24));
+        let fit = (height as usize)
+            .saturating_sub(self.chrome_lines() + 2)
+            .max(1);
+        match self.menu_height {
+            Some(menu_height) => (menu_height as usize).clamp(1, fit),
+            None => fit,
+        }
+    }
+
+    /// Range of menu rows currently inside the viewport
+    fn viewport(&self, row_count: usize) -> (usize, usize) {
+        let height = self.viewport_height();
+        let start = self.scroll_offset.min(row_count.saturating_sub(height));
+        (start, (start + height).min(row_count))
+    }
+
+    /// Scroll just enough to bring the current choice into view, along with
+    /// its group header when it is the first choice of a group
+    fn scroll_to_current(&mut self) {
+        let rows = self.menu_rows();
+        let height = self.viewport_height();
+        let current = rows
+            .iter()
+            .position(|row| matches!(row, MenuRow::Choice(i, _) if *i == self.current_index));
+        if let Some(pos) = current {
+            let top = match pos.checked_sub(1).map(|above| &rows[above]) {
+                Some(MenuRow::Header(_)) if height > 1 => pos - 1,
+                _ => pos,
+            };
+            if top < self.scroll_offset {
+                self.scroll_offset = top;
+            } else if pos >= self.scroll_offset + height {
+                self.scroll_offset = pos + 1 - height;
+            }
+        }
+        self.scroll_offset = self.scroll_offset.min(rows.len().saturating_sub(height));
+    }
+
+    /// Keep the cursor on a selectable choice after the filter changes
+    fn apply_filter(&mut self) {
+        if !self.is_selectable(self.current_index) {
+            if let Some(&first) = self.selectable_indices().first() {
+                self.current_index = first;
+            }
+        }
     }
 
     fn get_selected_choices(&self) -> Vec<String> {
@@ -334,7 +950,7 @@ impl ChoiceMenu {
             .enumerate()
             .filter_map(|(i, &selected)| {
                 if selected {
-                    Some(self.choices[i].clone())
+                    Some(self.choices[i].value.clone())
                 } else {
                     None
                 }
@@ -342,44 +958,42 @@ impl ChoiceMenu {
             .collect()
     }
 
+    /// Why the current selections can't be submitted, if they can't
+    fn selection_error(&self) -> Option<String> {
+        let selected = self.get_selected_choices();
+
+        if selected.len() < self.min_choices {
+            return Some(format!("At least {} choice(s) required", self.min_choices));
+        }
+        if selected.len() > self.max_choices {
+            return Some(format!("At most {} choice(s) allowed", self.max_choices));
+        }
+        self.groups.iter().find_map(|group| {
+            let count = selected
+                .iter()
+                .filter(|value| group.members.contains(value))
+                .count();
+            group.limit_error(count)
+        })
+    }
+
     /// Validate current selections against min/max constraints
     fn validate_selections(&mut self) {
-        let selected_count = self.selected_choices.iter().filter(|&&s| s).count();
-
-        self.validation_error = if selected_count < self.min_choices {
-            Some(format!("At least {} choice(s) required", self.min_choices))
-        } else if selected_count > self.max_choices {
-            Some(format!("At most {} choice(s) allowed", self.max_choices))
-        } else {
-            None
-        };
+        self.validation_error = self.selection_error();
     }
 
     /// Check if current selections meet the constraints for submission
     fn can_submit(&self) -> bool {
-        let selected_count = self.selected_choices.iter().filter(|&&s| s).count();
-        selected_count >= self.min_choices && selected_count <= self.max_choices
+        self.selection_error().is_none()
     }
 
     fn calculate_and_reserve_space(&self, _width: u16, _prompt_text: &str) -> Result<u16> {
-        use std::io::{self, Write};
-
-        let mut total_lines = 0u16;
-
-        // 1. Prompt line
-        total_lines += 1;
-
-        // 2. Instruction line
-        total_lines += 1;
+        use std::io::Write;
 
-        // 3. Choice lines
-        total_lines += self.choices.len() as u16;
-
-        // 4. Validation error line (if present)
-        total_lines += 1;
-
-        // 5. Buffer for spacing
-        total_lines += 2;
+        // Prompt, instruction, filter, scroll indicators and error lines,
+        // plus as many choices as fit in the viewport
+        let mut total_lines = self.chrome_lines() as u16;
+        total_lines += self.menu_rows().len().min(self.viewport_height()) as u16;
 
         // Ensure we don't try to reserve more lines than the terminal height
         let (_, terminal_height) = self.terminal.size()?;
@@ -387,21 +1001,20 @@ impl ChoiceMenu {
         total_lines = total_lines.min(max_reservable);
 
         // Print blank lines to reserve the space
-        let mut stderr = io::stderr();
+        let mut out = tty();
         for _ in 0..total_lines {
-            writeln!(stderr)?;
+            writeln!(out)?;
         }
-        stderr.flush()?;
+        out.flush()?;
 
         Ok(total_lines)
     }
 
     fn move_to_prompt_position(&mut self, reserved_lines: u16) -> Result<()> {
         use crossterm::{cursor::MoveUp, ExecutableCommand};
-        use std::io::stderr;
 
         // Move cursor back up to where we want to start the prompt
-        stderr().execute(MoveUp(reserved_lines))?;
+        tty().execute(MoveUp(reserved_lines))?;
 
         Ok(())
     }
@@ -416,9 +1029,155 @@ impl Drop for ChoiceMenu {
     }
 }
 
+/// A line of the choice list
+#[derive(Debug)]
+enum MenuRow {
+    Header(String),
+    Choice(usize, Vec<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Emphasis {
+    Normal,
+    Matched,
+    Dimmed,
+}
+
 #[derive(Debug)]
 enum MenuAction {
     Continue,
     Submit,
     Cancel,
+    Back,
+}
+
+/// Match a choice's label against the menu filter, falling back to its
+/// description (which has no highlighted characters)
+fn choice_match(choice: &ChoiceOption, filter: &str) -> Option<Vec<usize>> {
+    filter_match(choice.display_label(), filter).or_else(|| {
+        let description = choice.description.as_deref()?;
+        filter_match(description, filter).map(|_| Vec::new())
+    })
+}
+
+/// Match text against the menu filter, ignoring case. A contiguous
+/// substring match is preferred; otherwise the filter's characters must appear
+/// in order. Returns the matched character positions.
+fn filter_match(choice: &str, filter: &str) -> Option<Vec<usize>> {
+    let choice: Vec<char> = choice.chars().collect();
+    let filter: Vec<char> = filter.chars().collect();
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+
+    if filter.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if filter.len() <= choice.len() {
+        for start in 0..=choice.len() - filter.len() {
+            if filter
+                .iter()
+                .zip(&choice[start..])
+                .all(|(&f, &c)| same(f, c))
+            {
+                return Some((start..start + filter.len()).collect());
+            }
+        }
+    }
+
+    let mut positions = Vec::with_capacity(filter.len());
+    let mut remaining = choice.iter().enumerate();
+    for &f in &filter {
+        let (i, _) = remaining.find(|&(_, &c)| same(f, c))?;
+        positions.push(i);
+    }
+    Some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_ranked_selection_order() {
+        let choices = ["us-east", "us-west", "eu-west", "ap-south"]
+            .into_iter()
+            .map(ChoiceOption::new)
+            .collect();
+        let mut menu = ChoiceMenu::new(
+            Terminal::detached(),
+            choices,
+            true,
+            1,
+            4,
+            true,
+            Duration::from_secs(1),
+            vec!["ap-south".to_string()],
+        )
+        .unwrap()
+        .with_ranking(true);
+        assert_eq!(menu.get_selected_choices(), vec!["ap-south"]);
+
+        // Picking eu-west ranks it second, then SHIFT+↑ promotes it to first
+        let none = KeyModifiers::NONE;
+        for event in [
+            key(KeyCode::Down, none),
+            key(KeyCode::Down, none),
+            key(KeyCode::Down, none),
+            key(KeyCode::Char(' '), none),
+            key(KeyCode::Up, KeyModifiers::SHIFT),
+        ] {
+            menu.handle_key_event(event).unwrap();
+        }
+        assert_eq!(menu.get_selected_choices(), vec!["eu-west", "ap-south"]);
+        assert_eq!(menu.marker(0), "[1]");
+    }
+
+    #[test]
+    fn test_rows_above_cursor_after_resize() {
+        let choices = vec![ChoiceOption::new("dev"), ChoiceOption::new("prod")];
+        let mut menu = ChoiceMenu::new(
+            Terminal::detached(),
+            choices,
+            false,
+            1,
+            1,
+            true,
+            Duration::from_secs(1),
+            vec![],
+        )
+        .unwrap();
+        // Prompt, a 50 column instruction, two choices, then the empty
+        // line the cursor was left on
+        menu.drawn_lines = vec![5, 50, 5, 6, 0];
+        assert_eq!(menu.rows_above_cursor(80), 4);
+        // Narrowed to 20 columns, the instruction re-wraps onto three rows
+        assert_eq!(menu.rows_above_cursor(20), 6);
+
+        // With an error the cursor ends after it, on its second row at 20
+        menu.drawn_lines.push(30);
+        assert_eq!(menu.rows_above_cursor(80), 5);
+        assert_eq!(menu.rows_above_cursor(20), 8);
+    }
+
+    #[test]
+    fn test_filter_match_substring() {
+        assert_eq!(filter_match("feature/login", ""), Some(vec![]));
+        assert_eq!(filter_match("feature/login", "LOG"), Some(vec![8, 9, 10]));
+        assert_eq!(filter_match("main", "main"), Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_filter_match_fuzzy() {
+        assert_eq!(
+            filter_match("feature/login", "ftlg"),
+            Some(vec![0, 3, 8, 10])
+        );
+        assert_eq!(filter_match("main", "mn"), Some(vec![0, 3]));
+        assert_eq!(filter_match("main", "nm"), None);
+        assert_eq!(filter_match("dev", "develop"), None);
+    }
 }
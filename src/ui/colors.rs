@@ -1,3 +1,5 @@
+use super::terminal::{ColorDepth, TerminalCapabilities, UIMode};
+use crate::validation::Priority;
 use crossterm::style::{
     Attribute, Color, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
 };
@@ -14,6 +16,7 @@ pub struct ColorScheme {
     pub warning_icon: Color,
     pub info_icon: Color,
     pub success_icon: Color,
+    pub match_text: Color,
     pub background: Option<Color>,
 }
 
@@ -28,10 +31,38 @@ impl ColorScheme {
             warning_icon: Color::Yellow,
             info_icon: Color::Blue,
             success_icon: Color::Green,
+            match_text: Color::Yellow,
             background: None,
         }
     }
 
+    /// The default scheme with finer shades where the terminal has more
+    /// than the 16 ANSI colors, whose dark grey is unreadable on some themes
+    pub fn for_depth(depth: ColorDepth) -> Self {
+        let (help_text, match_text) = match depth {
+            ColorDepth::None => return Self::no_color(),
+            ColorDepth::Basic => return Self::default(),
+            ColorDepth::Ansi256 => (Color::AnsiValue(245), Color::AnsiValue(214)),
+            ColorDepth::TrueColor => (
+                Color::Rgb {
+                    r: 138,
+                    g: 138,
+                    b: 138,
+                },
+                Color::Rgb {
+                    r: 255,
+                    g: 175,
+                    b: 0,
+                },
+            ),
+        };
+        Self {
+            help_text,
+            match_text,
+            ..Self::default()
+        }
+    }
+
     pub fn no_color() -> Self {
         Self {
             valid_text: Color::Reset,
@@ -42,6 +73,7 @@ impl ColorScheme {
             warning_icon: Color::Reset,
             info_icon: Color::Reset,
             success_icon: Color::Reset,
+            match_text: Color::Reset,
             background: None,
         }
     }
@@ -56,6 +88,7 @@ impl ColorScheme {
             warning_icon: Color::Yellow,
             info_icon: Color::Cyan,
             success_icon: Color::Green,
+            match_text: Color::Yellow,
             background: Some(Color::Black),
         }
     }
@@ -103,6 +136,7 @@ impl ColoredText {
 pub struct Colorizer {
     scheme: ColorScheme,
     no_color: bool,
+    ascii: bool, // ASCII stand-ins for emoji and symbols
 }
 
 impl Colorizer {
@@ -114,9 +148,41 @@ impl Colorizer {
                 scheme
             },
             no_color,
+            ascii: false,
+        }
+    }
+
+    /// Colorizer for what the terminal can show: colors only in
+    /// `UIMode::Full`, in shades its color depth has, and ASCII symbols
+    /// unless the locale is UTF-8
+    pub fn for_terminal(capabilities: &TerminalCapabilities, no_color: bool) -> Self {
+        let no_color = no_color || capabilities.fallback_ui() != UIMode::Full;
+        let scheme = if no_color {
+            ColorScheme::no_color()
+        } else {
+            ColorScheme::for_depth(capabilities.colors)
+        };
+        Self::new(scheme, no_color).with_ascii(!capabilities.unicode_support)
+    }
+
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// `unicode`, or `ascii` on terminals that can't show it
+    pub fn symbol(&self, unicode: &'static str, ascii: &'static str) -> &'static str {
+        if self.ascii {
+            ascii
+        } else {
+            unicode
         }
     }
 
+    fn icon(&self, priority: Priority) -> &'static str {
+        self.symbol(priority.icon(), priority.ascii_icon())
+    }
+
     pub fn write_colored<W: Write + ExecutableCommand>(
         &self,
         writer: &mut W,
@@ -165,20 +231,37 @@ impl Colorizer {
             .bold()
     }
 
+    /// Characters of a choice that matched the menu filter
+    pub fn matched_text(&self, text: impl Into<String>) -> ColoredText {
+        ColoredText::new(text, self.scheme.match_text).bold()
+    }
+
     pub fn error_message(&self, text: impl Into<String>) -> ColoredText {
-        ColoredText::new(format!("❌ {}", text.into()), self.scheme.error_icon)
+        ColoredText::new(
+            format!("{} {}", self.icon(Priority::High), text.into()),
+            self.scheme.error_icon,
+        )
     }
 
     pub fn warning_message(&self, text: impl Into<String>) -> ColoredText {
-        ColoredText::new(format!("⚠️ {}", text.into()), self.scheme.warning_icon)
+        ColoredText::new(
+            format!("{} {}", self.icon(Priority::Medium), text.into()),
+            self.scheme.warning_icon,
+        )
     }
 
     pub fn info_message(&self, text: impl Into<String>) -> ColoredText {
-        ColoredText::new(format!("💡 {}", text.into()), self.scheme.info_icon)
+        ColoredText::new(
+            format!("{} {}", self.icon(Priority::Low), text.into()),
+            self.scheme.info_icon,
+        )
     }
 
     pub fn success_message(&self, text: impl Into<String>) -> ColoredText {
-        ColoredText::new(format!("✅ {}", text.into()), self.scheme.success_icon)
+        ColoredText::new(
+            format!("{} {}", self.symbol("✅", "[OK]"), text.into()),
+            self.scheme.success_icon,
+        )
     }
 
     pub fn no_color_error(&self, text: impl Into<String>) -> String {
@@ -197,3 +280,33 @@ impl Colorizer {
         format!("[OK] {}", text.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_follows_color_depth() {
+        assert_eq!(
+            ColorScheme::for_depth(ColorDepth::Basic).help_text,
+            Color::DarkGrey
+        );
+        assert_eq!(
+            ColorScheme::for_depth(ColorDepth::Ansi256).help_text,
+            Color::AnsiValue(245)
+        );
+        assert!(matches!(
+            ColorScheme::for_depth(ColorDepth::TrueColor).match_text,
+            Color::Rgb { .. }
+        ));
+        assert_eq!(
+            ColorScheme::for_depth(ColorDepth::None).help_text,
+            Color::Reset
+        );
+        // Only the shades change, not which colors mean what
+        assert_eq!(
+            ColorScheme::for_depth(ColorDepth::TrueColor).invalid_text,
+            Color::Red
+        );
+    }
+}
@@ -1,17 +1,42 @@
-use super::{ChoiceMenu, ColorScheme, Colorizer, LayoutManager, Screen, Terminal};
+use super::terminal::{tty, Tty};
+use super::{ChoiceMenu, Colorizer, LayoutManager, Screen, Terminal};
+use crate::cli::args::PasteMode;
 use crate::cli::config::PromptConfig;
 use crate::error::{PromptError, Result};
-use crate::validation::{ValidationEngine, ValidatorType};
+use crate::input::completion::{common_prefix, suggest, CompletionSource};
+use crate::input::editing::{KillRing, UndoStack};
+use crate::input::editor;
+use crate::input::graphemes::{self, grapheme_count};
+use crate::input::history::History;
+use crate::input::paste::{normalize_newlines, paste_line, Paste};
+use crate::input::text_area::TextArea;
+use crate::input::words::{word_end_after, word_start_before};
+use crate::validation::rules::ChoiceGroup;
+use crate::validation::{
+    ChoiceOption, Priority, ValidationEngine, ValidationResult, ValidatorType,
+};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
-use std::io::{self, stderr};
 use std::time::Duration;
 
 pub struct InteractivePrompt {
     terminal: Terminal,
     validation_engine: ValidationEngine,
     config: PromptConfig,
+    history: Option<History>, // Earlier answers for --history-key, oldest first
+    history_pos: Option<usize>, // Entry being recalled, if any
+    history_draft: String,    // Input typed before recalling history
+    completion: Option<CompletionSource>, // Where Tab looks for candidates
+    completion_popup: Option<Vec<String>>, // Ambiguous candidates to list after a Tab
+    suggestion: Option<String>, // Input the ghost text would complete to
+    ctrl_x_pending: bool,     // CTRL+X pressed, waiting for CTRL+E
+    undo_stack: UndoStack,    // Earlier states of the line for CTRL+_ / ALT+_
+    kill_ring: KillRing,      // Text killed with CTRL+K/U/W, yanked with CTRL+Y
+    paste_error: Option<ValidationResult>, // Why the last paste was refused
 }
 
+/// Rows kept free below the input for listing completion candidates
+const COMPLETION_ROWS: u16 = 4;
+
 impl InteractivePrompt {
     pub fn new(
         mut terminal: Terminal,
@@ -23,13 +48,81 @@ impl InteractivePrompt {
             terminal.enter_raw_mode()?;
         }
 
+        // Masked input is never remembered or recalled
+        let history = match &config.interaction_config.history_key {
+            Some(key) if !config.interaction_config.mask_input => {
+                Some(History::load(key, config.interaction_config.history_size))
+            }
+            _ => None,
+        };
+
+        let completion = if config.interaction_config.mask_input {
+            None
+        } else {
+            Self::completion_source(&config)
+        };
+
         Ok(Self {
             terminal,
             validation_engine,
             config,
+            completion,
+            completion_popup: None,
+            suggestion: None,
+            ctrl_x_pending: false,
+            undo_stack: UndoStack::default(),
+            kill_ring: KillRing::default(),
+            paste_error: None,
+            history,
+            history_pos: None,
+            history_draft: String::new(),
         })
     }
 
+    /// Pick what Tab completes: an explicit --complete-cmd, otherwise the
+    /// choices of a --free-text choice prompt, otherwise paths for the
+    /// filesystem existence checks
+    fn completion_source(config: &PromptConfig) -> Option<CompletionSource> {
+        if let Some(command) = &config.interaction_config.complete_cmd {
+            return Some(CompletionSource::Command(command.clone()));
+        }
+        config
+            .validation_rules
+            .iter()
+            .find_map(|rule| match &rule.validator_type {
+                ValidatorType::Choices(choices) => {
+                    let parameter = |name: &str| rule.parameters.get(name);
+                    let multiple = parameter("max_choices")
+                        .and_then(|max| max.parse::<usize>().ok())
+                        .is_some_and(|max| max > 1);
+                    Some(CompletionSource::Choices {
+                        choices: choices
+                            .iter()
+                            .filter(|choice| choice.disabled.is_none())
+                            .map(|choice| choice.value.clone())
+                            .collect(),
+                        separator: multiple.then(|| {
+                            parameter("selection_separator")
+                                .cloned()
+                                .unwrap_or_else(|| ",".to_string())
+                        }),
+                        case_sensitive: parameter("case_sensitive")
+                            .is_some_and(|value| value == "true"),
+                    })
+                }
+                ValidatorType::DirExists => Some(CompletionSource::Paths { dirs_only: true }),
+                ValidatorType::FileExists | ValidatorType::PathExists => {
+                    Some(CompletionSource::Paths { dirs_only: false })
+                }
+                _ => None,
+            })
+    }
+
+    /// Hand back the validation engine, keeping any results cached during the prompt
+    pub fn into_validation_engine(mut self) -> ValidationEngine {
+        std::mem::take(&mut self.validation_engine)
+    }
+
     pub fn prompt(&mut self) -> Result<String> {
         let prompt_text = self
             .config
@@ -45,22 +138,16 @@ impl InteractivePrompt {
         let has_help = self.config.ui_config.help_text.is_some();
 
         // Set up UI components
-        let _capabilities = self.terminal.capabilities().clone();
         let (width, height) = self.terminal.size()?;
 
-        let color_scheme = if self.config.ui_config.no_color {
-            ColorScheme::no_color()
-        } else {
-            ColorScheme::default()
-        };
-
-        let colorizer = Colorizer::new(color_scheme, self.config.ui_config.no_color);
+        let colorizer =
+            Colorizer::for_terminal(self.terminal.capabilities(), self.config.ui_config.no_color);
 
         // Calculate space needed and reserve it
         let reserved_lines = self.calculate_and_reserve_space(width, &prompt_text)?;
 
         let layout = LayoutManager::new(width, height);
-        let mut screen = Screen::new(stderr(), layout, colorizer);
+        let mut screen = Screen::new(tty(), layout, colorizer);
 
         // Calculate layout with reserved space context
         screen.layout_mut().calculate_layout(has_help);
@@ -69,16 +156,42 @@ impl InteractivePrompt {
         self.move_to_prompt_position(reserved_lines)?;
 
         // Draw initial screen
-        let prompt_width = screen.write_prompt(&prompt_text)?;
+        let mut prompt_width = screen.write_prompt(&prompt_text)?;
 
         // Don't write help text initially - only show it when there are validation errors
 
         screen.flush()?;
 
+        // --editor starts in the user's editor, seeded with the default.
+        // Masked input is never written to the editor's temporary file.
+        let mut initial = String::new();
+        if self.config.interaction_config.open_editor && !self.config.interaction_config.mask_input
+        {
+            let draft = self
+                .config
+                .interaction_config
+                .default_value
+                .clone()
+                .unwrap_or_default();
+            if let Some(text) = self.edit_in_editor(&draft)? {
+                initial = text;
+            }
+        }
+
+        if self.config.interaction_config.multiline || initial.contains('\n') {
+            return self.prompt_multiline(&mut screen, TextArea::from_text(&initial));
+        }
+
         // Input loop
-        let mut input = String::new();
-        let mut cursor_pos = 0; // Track cursor position within the input
+        let mut input = initial;
+        let mut cursor_pos = grapheme_count(&input); // Cursor position in grapheme clusters
         let mut attempts = 0;
+
+        // Show the default as a suggestion until something is typed
+        self.redraw_input(&input, &cursor_pos, &mut screen, prompt_width)?;
+        if !input.is_empty() {
+            self.update_validation_display(&input, &mut screen, cursor_pos, prompt_width)?;
+        }
         let max_attempts = self
             .config
             .interaction_config
@@ -87,7 +200,7 @@ impl InteractivePrompt {
 
         loop {
             // Position cursor at the correct position within the input
-            screen.position_cursor_at_input_pos(&input, cursor_pos, prompt_width)?;
+            screen.position_cursor_at_input_pos(cursor_pos, prompt_width)?;
             screen.flush()?;
 
             // Handle timeout
@@ -99,15 +212,48 @@ impl InteractivePrompt {
 
             // Read input event
             if event::poll(timeout)? {
-                if let Event::Key(key_event) = event::read()? {
-                    match self.handle_key_event(
+                let action = match event::read()? {
+                    Event::Key(key_event) => self.handle_key_event(
                         key_event,
                         &mut input,
                         &mut cursor_pos,
                         &mut screen,
                         prompt_width,
-                    )? {
-                        InputAction::Continue => {
+                    )?,
+                    Event::Paste(text) => {
+                        if let Some(lines) = self.paste(&text, &mut input, &mut cursor_pos) {
+                            // Several lines carry on in the multi-line editor
+                            screen.write_input("", 0, prompt_width, None)?;
+                            return self.prompt_multiline(&mut screen, TextArea::from_text(&lines));
+                        }
+                        self.redraw_input(&input, &cursor_pos, &mut screen, prompt_width)?;
+                        InputAction::Continue
+                    }
+                    // Redraw from the prompt line, re-wrapping errors to the new width
+                    Event::Resize(width, height) => {
+                        screen.resize(width, height)?;
+                        prompt_width = screen.write_prompt(&prompt_text)?;
+                        self.redraw_input(&input, &cursor_pos, &mut screen, prompt_width)?;
+                        self.update_validation_display(
+                            &input,
+                            &mut screen,
+                            cursor_pos,
+                            prompt_width,
+                        )?;
+                        continue;
+                    }
+                    _ => continue,
+                };
+                match action {
+                    InputAction::Continue => {
+                        if let Some(candidates) = self.completion_popup.take() {
+                            // List ambiguous completions where errors usually go
+                            screen.write_candidates(&candidates, COMPLETION_ROWS)?;
+                            screen.restore_saved_cursor()?;
+                        } else if let Some(error) = self.paste_error.take() {
+                            screen.write_errors(&[error])?;
+                            screen.restore_saved_cursor()?;
+                        } else {
                             // Validate and update display, then reposition cursor
                             self.update_validation_display(
                                 &input,
@@ -116,39 +262,63 @@ impl InteractivePrompt {
                                 prompt_width,
                             )?;
                         }
-                        InputAction::Submit => {
-                            // Final validation
-                            let summary = self.validation_engine.validate(&input);
-                            if summary.valid {
-                                // Handle confirmation if required
-                                if self.config.interaction_config.require_confirmation {
-                                    match self.prompt_confirmation(&input)? {
-                                        Some(confirmed_input) => return Ok(confirmed_input),
-                                        None => {
-                                            // Confirmation failed, continue with original input loop
-                                            continue;
-                                        }
+                    }
+                    InputAction::Submit => {
+                        // Final validation
+                        let summary = self.validation_engine.try_validate(&input)?;
+                        if summary.valid {
+                            // Handle confirmation if required
+                            if self.config.interaction_config.require_confirmation {
+                                match self.prompt_confirmation(&input)? {
+                                    Some(confirmed_input) => {
+                                        self.remember(&confirmed_input);
+                                        return Ok(confirmed_input);
+                                    }
+                                    None => {
+                                        // Confirmation failed, continue with original input loop
+                                        continue;
                                     }
-                                } else {
-                                    return Ok(input);
                                 }
                             } else {
-                                attempts += 1;
-                                if attempts >= max_attempts {
-                                    return Err(PromptError::MaxAttemptsExceeded);
-                                }
-                                // Show errors and continue
-                                self.update_validation_display(
-                                    &input,
-                                    &mut screen,
-                                    cursor_pos,
-                                    prompt_width,
-                                )?;
+                                self.remember(&input);
+                                return Ok(input);
+                            }
+                        } else {
+                            attempts += 1;
+                            if attempts >= max_attempts {
+                                return Err(PromptError::MaxAttemptsExceeded);
                             }
+                            // Show errors (including submit-only ones) and continue
+                            let errors = self
+                                .validation_engine
+                                .get_summary_display_errors(&summary, Some(10));
+                            self.show_errors(&errors, &mut screen, cursor_pos, prompt_width)?;
                         }
-                        InputAction::Cancel => {
-                            return Err(PromptError::Interrupted);
+                    }
+                    InputAction::OpenEditor => {
+                        if let Some(text) = self.edit_in_editor(&input)? {
+                            if text.contains('\n') {
+                                // Several lines carry on in the multi-line editor
+                                screen.write_input("", 0, prompt_width, None)?;
+                                return self
+                                    .prompt_multiline(&mut screen, TextArea::from_text(&text));
+                            }
+                            input = text;
+                            cursor_pos = grapheme_count(&input);
                         }
+                        self.redraw_input(&input, &cursor_pos, &mut screen, prompt_width)?;
+                        self.update_validation_display(
+                            &input,
+                            &mut screen,
+                            cursor_pos,
+                            prompt_width,
+                        )?;
+                    }
+                    InputAction::Cancel => {
+                        return Err(PromptError::Interrupted);
+                    }
+                    InputAction::Back => {
+                        return Err(PromptError::NavigateBack);
                     }
                 }
             } else {
@@ -157,12 +327,238 @@ impl InteractivePrompt {
         }
     }
 
+    /// Edit several lines of text: Enter starts a new line and CTRL+D or
+    /// ALT+Enter submits. Validators run over the whole text.
+    fn prompt_multiline(&mut self, screen: &mut Screen<Tty>, mut area: TextArea) -> Result<String> {
+        screen.write_hint("(CTRL+D or ALT+ENTER to submit)")?;
+
+        let mut errors = if area.is_empty() {
+            Vec::new()
+        } else {
+            self.validation_engine
+                .get_display_errors(&area.text(), Some(10))
+        };
+        let mut cursor_row = 0;
+        let mut attempts = 0;
+        let max_attempts = self
+            .config
+            .interaction_config
+            .max_attempts
+            .unwrap_or(u32::MAX);
+        let timeout = self
+            .config
+            .interaction_config
+            .timeout
+            .unwrap_or(Duration::from_secs(300));
+
+        loop {
+            let help_text = self.config.ui_config.help_text.as_deref();
+            cursor_row = screen.write_text_area(
+                area.lines(),
+                area.cursor(),
+                cursor_row,
+                &errors,
+                help_text,
+            )?;
+            screen.flush()?;
+
+            if !event::poll(timeout)? {
+                return Err(PromptError::Timeout);
+            }
+            let action = match event::read()? {
+                Event::Key(key_event) => self.handle_multiline_key(key_event, &mut area),
+                Event::Paste(text) => {
+                    area.insert_text(&normalize_newlines(&text));
+                    InputAction::Continue
+                }
+                // Later redraws wrap the text to the new width
+                Event::Resize(width, height) => {
+                    screen.layout_mut().resize(width, height);
+                    continue;
+                }
+                _ => continue,
+            };
+            match action {
+                InputAction::Continue => {
+                    errors = self
+                        .validation_engine
+                        .get_display_errors(&area.text(), Some(10));
+                }
+                InputAction::Submit => {
+                    if area.is_empty() {
+                        if let Some(default_value) = &self.config.interaction_config.default_value {
+                            area = TextArea::from_text(default_value);
+                        }
+                    }
+                    let text = area.text();
+                    let summary = self.validation_engine.try_validate(&text)?;
+                    if summary.valid {
+                        // Leave the text on screen with the cursor after it
+                        let end = TextArea::from_text(&text);
+                        screen.write_text_area(end.lines(), end.cursor(), cursor_row, &[], None)?;
+                        screen.flush()?;
+                        self.remember(&text);
+                        return Ok(text);
+                    }
+                    attempts += 1;
+                    if attempts >= max_attempts {
+                        return Err(PromptError::MaxAttemptsExceeded);
+                    }
+                    errors = self
+                        .validation_engine
+                        .get_summary_display_errors(&summary, Some(10));
+                }
+                InputAction::OpenEditor => {
+                    if let Some(text) = self.edit_in_editor(&area.text())? {
+                        area = TextArea::from_text(&text);
+                    }
+                    errors = self
+                        .validation_engine
+                        .get_display_errors(&area.text(), Some(10));
+                }
+                InputAction::Cancel => return Err(PromptError::Interrupted),
+                InputAction::Back => return Err(PromptError::NavigateBack),
+            }
+        }
+    }
+
+    fn handle_multiline_key(&mut self, key_event: KeyEvent, area: &mut TextArea) -> InputAction {
+        if self.editor_chord(&key_event) {
+            return InputAction::OpenEditor;
+        }
+        let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            // Submit on CTRL+D or ALT+Enter; plain Enter starts a new line
+            KeyCode::Char('d') if control => return InputAction::Submit,
+            KeyCode::Enter if key_event.modifiers.contains(KeyModifiers::ALT) => {
+                return InputAction::Submit
+            }
+            KeyCode::Char('c') if control => return InputAction::Cancel,
+            KeyCode::BackTab if self.config.interaction_config.allow_back => {
+                return InputAction::Back
+            }
+            KeyCode::Enter => area.insert_newline(),
+
+            // Line editing keys act on the current line
+            KeyCode::Char('a') if control => area.line_start(),
+            KeyCode::Char('e') if control => area.line_end(),
+            KeyCode::Char('k') if control => {
+                area.kill_to_line_end();
+            }
+            KeyCode::Char('u') if control => {
+                area.kill_to_line_start();
+            }
+            KeyCode::Char(_) if control => {}
+            KeyCode::Char(c) => area.insert_char(c),
+            KeyCode::Backspace => {
+                area.backspace();
+            }
+            KeyCode::Delete => {
+                area.delete();
+            }
+
+            // Up on the first line and Down on the last one recall history
+            KeyCode::Up if area.on_first_row() => self.recall_into(-1, area),
+            KeyCode::Down if area.on_last_row() => self.recall_into(1, area),
+            KeyCode::Up => area.move_up(),
+            KeyCode::Down => area.move_down(),
+            KeyCode::Left => area.move_left(),
+            KeyCode::Right => area.move_right(),
+            KeyCode::Home => area.line_start(),
+            KeyCode::End => area.line_end(),
+            _ => {}
+        }
+        InputAction::Continue
+    }
+
+    /// Track CTRL+X CTRL+E, which opens the input in the user's editor.
+    /// Masked input is never written to a file.
+    fn editor_chord(&mut self, key_event: &KeyEvent) -> bool {
+        let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let pending = std::mem::replace(
+            &mut self.ctrl_x_pending,
+            control && key_event.code == KeyCode::Char('x'),
+        );
+        pending
+            && control
+            && key_event.code == KeyCode::Char('e')
+            && !self.config.interaction_config.mask_input
+    }
+
+    /// Hand the text to $VISUAL/$EDITOR with raw mode off. Returns `None`
+    /// when the editor exits unsuccessfully, keeping the text as it was.
+    fn edit_in_editor(&mut self, text: &str) -> Result<Option<String>> {
+        self.terminal.leave_raw_mode()?;
+        let edited = editor::edit_text(text);
+        self.terminal.enter_raw_mode()?;
+        edited
+    }
+
+    fn recall_into(&mut self, direction: isize, area: &mut TextArea) {
+        let mut text = area.text();
+        if self.recall_history(direction, &mut text) {
+            *area = TextArea::from_text(&text);
+        }
+    }
+
     fn handle_key_event(
-        &self,
+        &mut self,
         key_event: KeyEvent,
         input: &mut String,
         cursor_pos: &mut usize,
-        screen: &mut Screen<io::Stderr>,
+        screen: &mut Screen<Tty>,
+        prompt_width: u16,
+    ) -> Result<InputAction> {
+        if self.editor_chord(&key_event) {
+            return Ok(InputAction::OpenEditor);
+        }
+        self.kill_ring.start_key();
+
+        // Undo and redo restore whole states of the line
+        let current = (input.clone(), *cursor_pos);
+        let restored = match key_event {
+            KeyEvent {
+                code: KeyCode::Char('_' | '7' | 'z'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => Some(self.undo_stack.undo(current)),
+            KeyEvent {
+                code: KeyCode::Char('_' | '/'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => Some(self.undo_stack.redo(current)),
+            _ => None,
+        };
+        if let Some(restored) = restored {
+            if let Some((text, cursor)) = restored {
+                *input = text;
+                *cursor_pos = cursor;
+                self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+            }
+            return Ok(InputAction::Continue);
+        }
+
+        let before = (input.clone(), *cursor_pos);
+        let typed = matches!(
+            key_event,
+            KeyEvent {
+                code: KeyCode::Char(_),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                ..
+            }
+        );
+        let action = self.edit_line(key_event, input, cursor_pos, screen, prompt_width)?;
+        self.undo_stack.record(before, input, typed);
+        Ok(action)
+    }
+
+    /// Apply an editing or navigation key to the line
+    fn edit_line(
+        &mut self,
+        key_event: KeyEvent,
+        input: &mut String,
+        cursor_pos: &mut usize,
+        screen: &mut Screen<Tty>,
         prompt_width: u16,
     ) -> Result<InputAction> {
         match key_event {
@@ -180,7 +576,11 @@ impl InteractivePrompt {
                         .as_ref()
                         .unwrap()
                         .clone();
-                    *cursor_pos = input.chars().count();
+                    *cursor_pos = grapheme_count(input);
+                }
+                // Leave only the submitted text on the line, without ghost text
+                if self.suggestion.take().is_some() {
+                    screen.write_input(input, *cursor_pos, prompt_width, None)?;
                 }
                 Ok(InputAction::Submit)
             }
@@ -212,16 +612,20 @@ impl InteractivePrompt {
                 ..
             } => {
                 *cursor_pos = 0;
+                self.hide_suggestion(input, cursor_pos, screen, prompt_width)?;
                 Ok(InputAction::Continue)
             }
 
-            // Ctrl+E - Jump to end of line
+            // Ctrl+E - Jump to end of line, or accept the suggestion there
             KeyEvent {
                 code: KeyCode::Char('e'),
                 modifiers: KeyModifiers::CONTROL,
                 ..
             } => {
-                *cursor_pos = input.chars().count();
+                if !self.accept_suggestion(input, cursor_pos) {
+                    *cursor_pos = grapheme_count(input);
+                }
+                self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 Ok(InputAction::Continue)
             }
 
@@ -231,10 +635,10 @@ impl InteractivePrompt {
                 modifiers: KeyModifiers::CONTROL,
                 ..
             } => {
-                let chars: Vec<char> = input.chars().collect();
-                if *cursor_pos < chars.len() {
-                    let new_input: String = chars[..*cursor_pos].iter().collect();
-                    *input = new_input;
+                let len = grapheme_count(input);
+                if *cursor_pos < len {
+                    let killed = graphemes::remove_range(input, *cursor_pos..len);
+                    self.kill_ring.kill(&killed, false);
                     self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 }
                 Ok(InputAction::Continue)
@@ -246,40 +650,157 @@ impl InteractivePrompt {
                 modifiers: KeyModifiers::CONTROL,
                 ..
             } => {
-                let chars: Vec<char> = input.chars().collect();
                 if *cursor_pos > 0 {
-                    let new_input: String = chars[*cursor_pos..].iter().collect();
-                    *input = new_input;
+                    let killed = graphemes::remove_range(input, 0..*cursor_pos);
+                    self.kill_ring.kill(&killed, true);
                     *cursor_pos = 0;
                     self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 }
                 Ok(InputAction::Continue)
             }
 
-            // Ctrl+W - Delete word before cursor
+            // Ctrl+W / Alt+Backspace - Delete word before cursor, stopping at
+            // separators such as '/' so paths lose one component at a time
             KeyEvent {
                 code: KeyCode::Char('w'),
                 modifiers: KeyModifiers::CONTROL,
                 ..
+            }
+            | KeyEvent {
+                code: KeyCode::Backspace,
+                modifiers: KeyModifiers::ALT,
+                ..
             } => {
                 if *cursor_pos > 0 {
-                    let chars: Vec<char> = input.chars().collect();
-                    let mut new_cursor = *cursor_pos;
+                    let new_cursor = word_start_before(input, *cursor_pos);
+                    let killed = graphemes::remove_range(input, new_cursor..*cursor_pos);
+                    self.kill_ring.kill(&killed, true);
+                    *cursor_pos = new_cursor;
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
 
-                    // Skip whitespace before cursor
-                    while new_cursor > 0 && chars[new_cursor - 1].is_whitespace() {
-                        new_cursor -= 1;
-                    }
+            // Alt+D - Delete word after cursor
+            KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => {
+                let end = word_end_after(input, *cursor_pos);
+                if end > *cursor_pos {
+                    let killed = graphemes::remove_range(input, *cursor_pos..end);
+                    self.kill_ring.kill(&killed, false);
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
 
-                    // Delete word characters
-                    while new_cursor > 0 && !chars[new_cursor - 1].is_whitespace() {
-                        new_cursor -= 1;
-                    }
+            // Alt+B / Ctrl+Left - Move to the start of the previous word
+            KeyEvent {
+                code: KeyCode::Char('b'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Left,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                *cursor_pos = word_start_before(input, *cursor_pos);
+                self.hide_suggestion(input, cursor_pos, screen, prompt_width)?;
+                Ok(InputAction::Continue)
+            }
 
-                    let mut new_chars = chars;
-                    new_chars.drain(new_cursor..*cursor_pos);
-                    *input = new_chars.iter().collect();
-                    *cursor_pos = new_cursor;
+            // Alt+F / Ctrl+Right - Move to the end of the next word
+            KeyEvent {
+                code: KeyCode::Char('f'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Right,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                *cursor_pos = word_end_after(input, *cursor_pos);
+                self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                Ok(InputAction::Continue)
+            }
+
+            // Ctrl+T - Swap the characters around the cursor and move past
+            // them; at the end of the line, swap the last two
+            KeyEvent {
+                code: KeyCode::Char('t'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                if let Some(after) = graphemes::transpose(input, *cursor_pos) {
+                    *cursor_pos = after;
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
+
+            // Ctrl+Y - Yank the most recently killed text
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                if let Some(text) = self.kill_ring.yank(*cursor_pos) {
+                    *cursor_pos = graphemes::insert_at(input, *cursor_pos, &text);
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
+
+            // Alt+Y - Replace the text just yanked with an older kill
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => {
+                if let Some((start, len, text)) = self.kill_ring.yank_pop() {
+                    graphemes::remove_range(input, start..start + len);
+                    *cursor_pos = graphemes::insert_at(input, start, &text);
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
+
+            // Shift+Tab - Return to the previous form field
+            KeyEvent {
+                code: KeyCode::BackTab,
+                ..
+            } if self.config.interaction_config.allow_back => Ok(InputAction::Back),
+
+            // Tab completes the text before the cursor
+            KeyEvent {
+                code: KeyCode::Tab, ..
+            } => {
+                if self.complete(input, cursor_pos) {
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
+
+            // Up/Down recall earlier answers for --history-key
+            KeyEvent {
+                code: KeyCode::Up, ..
+            } => {
+                if self.recall_history(-1, input) {
+                    *cursor_pos = grapheme_count(input);
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                }
+                Ok(InputAction::Continue)
+            }
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            } => {
+                if self.recall_history(1, input) {
+                    *cursor_pos = grapheme_count(input);
                     self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 }
                 Ok(InputAction::Continue)
@@ -293,15 +814,28 @@ impl InteractivePrompt {
                 if *cursor_pos > 0 {
                     *cursor_pos -= 1;
                 }
+                self.hide_suggestion(input, cursor_pos, screen, prompt_width)?;
                 Ok(InputAction::Continue)
             }
 
+            // Right and Ctrl+F move forward, accepting the suggestion at the end
             KeyEvent {
                 code: KeyCode::Right,
                 ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('f'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
             } => {
-                if *cursor_pos < input.chars().count() {
+                if self.accept_suggestion(input, cursor_pos) {
+                    self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                } else if *cursor_pos < grapheme_count(input) {
                     *cursor_pos += 1;
+                    // Back at the end, the suggestion shows again
+                    if *cursor_pos == grapheme_count(input) {
+                        self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+                    }
                 }
                 Ok(InputAction::Continue)
             }
@@ -312,41 +846,41 @@ impl InteractivePrompt {
                 ..
             } => {
                 *cursor_pos = 0;
+                self.hide_suggestion(input, cursor_pos, screen, prompt_width)?;
                 Ok(InputAction::Continue)
             }
 
-            // End key - jump to end
+            // End key - jump to end, or accept the suggestion there
             KeyEvent {
                 code: KeyCode::End, ..
             } => {
-                *cursor_pos = input.chars().count();
+                if !self.accept_suggestion(input, cursor_pos) {
+                    *cursor_pos = grapheme_count(input);
+                }
+                self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 Ok(InputAction::Continue)
             }
 
-            // Backspace - delete character before cursor
+            // Backspace - delete the character (grapheme cluster) before cursor
             KeyEvent {
                 code: KeyCode::Backspace,
                 ..
             } => {
                 if *cursor_pos > 0 {
-                    let mut chars: Vec<char> = input.chars().collect();
-                    chars.remove(*cursor_pos - 1);
-                    *input = chars.iter().collect();
+                    graphemes::remove_range(input, *cursor_pos - 1..*cursor_pos);
                     *cursor_pos -= 1;
                     self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 }
                 Ok(InputAction::Continue)
             }
 
-            // Delete key - delete character at cursor
+            // Delete key - delete the character (grapheme cluster) at cursor
             KeyEvent {
                 code: KeyCode::Delete,
                 ..
             } => {
-                let mut chars: Vec<char> = input.chars().collect();
-                if *cursor_pos < chars.len() {
-                    chars.remove(*cursor_pos);
-                    *input = chars.iter().collect();
+                if *cursor_pos < grapheme_count(input) {
+                    graphemes::remove_range(input, *cursor_pos..*cursor_pos + 1);
                     self.redraw_input(input, cursor_pos, screen, prompt_width)?;
                 }
                 Ok(InputAction::Continue)
@@ -363,11 +897,9 @@ impl InteractivePrompt {
                     return Ok(InputAction::Continue);
                 }
 
-                // Insert character at cursor position
-                let mut chars: Vec<char> = input.chars().collect();
-                chars.insert(*cursor_pos, c);
-                *input = chars.iter().collect();
-                *cursor_pos += 1;
+                // Insert character at cursor position; a combining mark joins
+                // the character before it rather than adding a new one
+                *cursor_pos = graphemes::insert_at(input, *cursor_pos, c.encode_utf8(&mut [0; 4]));
 
                 // Redraw input (masking handled in redraw_input method)
                 self.redraw_input(input, cursor_pos, screen, prompt_width)?;
@@ -379,24 +911,167 @@ impl InteractivePrompt {
         }
     }
 
+    /// Insert pasted text at the cursor in one step, so it is validated and
+    /// undone as a whole. Returns the whole input when the paste leaves it
+    /// with several lines, to continue in the multi-line editor.
+    fn paste(&mut self, text: &str, input: &mut String, cursor_pos: &mut usize) -> Option<String> {
+        let mode = match self.config.interaction_config.paste_newlines {
+            // Secrets never go to the multi-line editor
+            PasteMode::Multiline if self.config.interaction_config.mask_input => PasteMode::Reject,
+            mode => mode,
+        };
+        let text = match paste_line(text, mode) {
+            Paste::Line(text) => text,
+            Paste::Lines(lines) => {
+                let mut whole = input.clone();
+                whole.insert_str(graphemes::byte_offset(input, *cursor_pos), &lines);
+                return Some(whole);
+            }
+            Paste::Rejected => {
+                self.paste_error = Some(ValidationResult::failure(
+                    "paste",
+                    Priority::High,
+                    "Pasted text has several lines; paste a single line",
+                ));
+                return None;
+            }
+        };
+
+        self.kill_ring.start_key();
+        let before = (input.clone(), *cursor_pos);
+        *cursor_pos = graphemes::insert_at(input, *cursor_pos, &text);
+        self.undo_stack.record(before, input, false);
+        None
+    }
+
+    /// Complete the text before the cursor. A single candidate is inserted and
+    /// several extend the input to their common prefix; when that adds
+    /// nothing they are listed below the input instead. Returns whether the
+    /// input changed.
+    fn complete(&mut self, input: &mut String, cursor_pos: &mut usize) -> bool {
+        let Some(source) = &self.completion else {
+            return false;
+        };
+        let (before, after) = input.split_at(graphemes::byte_offset(input, *cursor_pos));
+        let completion = source.complete(before);
+
+        let replacement = match completion.candidates.as_slice() {
+            [] => return false,
+            [only] => only.as_str(),
+            candidates => common_prefix(candidates),
+        };
+        let typed = &before[completion.start..];
+        if completion.candidates.len() > 1 && grapheme_count(replacement) <= grapheme_count(typed) {
+            self.completion_popup = Some(completion.candidates);
+            return false;
+        }
+
+        let completed = format!("{}{}", &before[..completion.start], replacement);
+        *cursor_pos = grapheme_count(&completed);
+        *input = format!("{}{}", completed, after);
+        true
+    }
+
+    /// Step through history: -1 for an older entry, 1 for a newer one. Going
+    /// past the newest entry restores what was typed before recalling.
+    /// Returns whether the input changed.
+    fn recall_history(&mut self, direction: isize, input: &mut String) -> bool {
+        let Some(history) = &self.history else {
+            return false;
+        };
+        let entries = history.entries();
+        let target = match (self.history_pos, direction < 0) {
+            (None, true) if !entries.is_empty() => {
+                self.history_draft = input.clone();
+                Some(entries.len() - 1)
+            }
+            (Some(pos), true) if pos > 0 => Some(pos - 1),
+            (Some(pos), false) if pos + 1 < entries.len() => Some(pos + 1),
+            (Some(_), false) => None,
+            _ => return false,
+        };
+
+        *input = match target {
+            Some(pos) => entries[pos].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.history_pos = target;
+        true
+    }
+
+    /// Add a submitted answer to the history file; failing to save only
+    /// loses the convenience, so errors are ignored
+    fn remember(&mut self, answer: &str) {
+        if let Some(history) = &mut self.history {
+            history.add(answer);
+            let _ = history.save();
+        }
+    }
+
+    /// Replace the input with its suggestion when the cursor is at the end.
+    /// Returns whether the input changed.
+    fn accept_suggestion(&mut self, input: &mut String, cursor_pos: &mut usize) -> bool {
+        let at_end = *cursor_pos == grapheme_count(input);
+        match self.suggestion.take() {
+            Some(suggestion) if at_end => {
+                *input = suggestion;
+                *cursor_pos = grapheme_count(input);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Redraw without the ghost text once the cursor has left the end
+    fn hide_suggestion(
+        &mut self,
+        input: &str,
+        cursor_pos: &usize,
+        screen: &mut Screen<Tty>,
+        prompt_width: u16,
+    ) -> Result<()> {
+        if self.suggestion.is_some() {
+            self.redraw_input(input, cursor_pos, screen, prompt_width)?;
+        }
+        Ok(())
+    }
+
     fn redraw_input(
-        &self,
+        &mut self,
         input: &str,
-        _cursor_pos: &usize,
-        screen: &mut Screen<io::Stderr>,
+        cursor_pos: &usize,
+        screen: &mut Screen<Tty>,
         prompt_width: u16,
     ) -> Result<()> {
         let display_input = if self.config.interaction_config.mask_input {
-            "*".repeat(input.chars().count())
+            "*".repeat(grapheme_count(input))
         } else {
             input.to_string()
         };
-        screen.write_input(&display_input, prompt_width, None)?;
+        let cursor_x = screen.write_input(&display_input, *cursor_pos, prompt_width, None)?;
+
+        // Secrets never get suggestions
+        self.suggestion =
+            if self.config.interaction_config.mask_input || *cursor_pos < grapheme_count(input) {
+                None
+            } else {
+                let history = self.history.as_ref().map_or(&[][..], |h| h.entries());
+                suggest(
+                    input,
+                    history,
+                    self.completion.as_ref(),
+                    self.config.interaction_config.default_value.as_deref(),
+                )
+            };
+        if let Some(suggestion) = &self.suggestion {
+            let ghost = &suggestion[graphemes::byte_offset(suggestion, grapheme_count(input))..];
+            screen.write_suggestion(ghost, cursor_x)?;
+        }
         Ok(())
     }
 
     fn calculate_and_reserve_space(&self, width: u16, _prompt_text: &str) -> Result<u16> {
-        use std::io::{self, Write};
+        use std::io::Write;
 
         // Get all potential error messages
         let messages = self.validation_engine.get_potential_error_messages();
@@ -422,17 +1097,22 @@ impl InteractivePrompt {
         // 4. Add some buffer for dynamic content and spacing
         total_lines += 3;
 
+        // 5. Room to list completion candidates
+        if self.completion.is_some() {
+            total_lines += COMPLETION_ROWS;
+        }
+
         // Ensure we don't try to reserve more lines than the terminal height
         let (_, terminal_height) = self.terminal.size()?;
         let max_reservable = terminal_height.saturating_sub(2); // Leave room for prompt
         total_lines = total_lines.min(max_reservable);
 
         // Print blank lines to reserve the space
-        let mut stderr = io::stderr();
+        let mut out = tty();
         for _ in 0..total_lines {
-            writeln!(stderr)?;
+            writeln!(out)?;
         }
-        stderr.flush()?;
+        out.flush()?;
 
         Ok(total_lines)
     }
@@ -469,10 +1149,9 @@ impl InteractivePrompt {
 
     fn move_to_prompt_position(&mut self, reserved_lines: u16) -> Result<()> {
         use crossterm::{cursor::MoveUp, ExecutableCommand};
-        use std::io::stderr;
 
         // Move cursor back up to where we want to start the prompt
-        stderr().execute(MoveUp(reserved_lines))?;
+        tty().execute(MoveUp(reserved_lines))?;
 
         Ok(())
     }
@@ -480,17 +1159,30 @@ impl InteractivePrompt {
     fn update_validation_display(
         &self,
         input: &str,
-        screen: &mut Screen<io::Stderr>,
+        screen: &mut Screen<Tty>,
         cursor_pos: usize,
         prompt_width: u16,
     ) -> Result<()> {
         if !self.config.interaction_config.mask_input {
             // Get validation results
             let errors = self.validation_engine.get_display_errors(input, Some(10));
+            self.show_errors(&errors, screen, cursor_pos, prompt_width)?;
+        }
 
+        Ok(())
+    }
+
+    fn show_errors(
+        &self,
+        errors: &[ValidationResult],
+        screen: &mut Screen<Tty>,
+        cursor_pos: usize,
+        prompt_width: u16,
+    ) -> Result<()> {
+        if !self.config.interaction_config.mask_input {
             // Write errors below the input and help text if there are errors
             if !errors.is_empty() {
-                screen.write_errors(&errors)?;
+                screen.write_errors(errors)?;
 
                 if let Some(help_text) = &self.config.ui_config.help_text {
                     screen.write_help(help_text)?;
@@ -500,12 +1192,12 @@ impl InteractivePrompt {
                 screen.restore_saved_cursor()?;
             } else {
                 // Clear any existing errors/help text when input is valid
-                screen.write_errors(&errors)?; // This will clear the area
+                screen.write_errors(errors)?; // This will clear the area
                 screen.restore_saved_cursor()?;
             }
 
             // Position cursor at the correct input position after all display updates
-            screen.position_cursor_at_input_pos(input, cursor_pos, prompt_width)?;
+            screen.position_cursor_at_input_pos(cursor_pos, prompt_width)?;
 
             screen.flush()?;
         }
@@ -514,12 +1206,13 @@ impl InteractivePrompt {
     }
 
     fn prompt_confirmation(&mut self, original_input: &str) -> Result<Option<String>> {
-        use std::io::{self, Write};
+        use std::io::Write;
 
         // Print newline and confirmation prompt
-        eprintln!();
-        eprint!("Confirm input: ");
-        io::stderr().flush()?;
+        let mut out = tty();
+        writeln!(out)?;
+        write!(out, "Confirm input: ")?;
+        out.flush()?;
 
         // Create a new terminal instance for confirmation prompt
         let terminal = Terminal::new()?;
@@ -530,8 +1223,15 @@ impl InteractivePrompt {
         confirmation_config.prompt_text = Some("Confirm input:".to_string());
         confirmation_config.validation_rules.clear(); // No validation rules for confirmation
         confirmation_config.interaction_config.require_confirmation = false; // Avoid infinite recursion
+        confirmation_config.interaction_config.allow_back = false;
         confirmation_config.interaction_config.mask_input =
             self.config.interaction_config.mask_input; // Keep same masking behavior
+                                                       // The answer must be typed again: nothing may fill it in, so no
+                                                       // default, history, completions (and their ghost text) or editor
+        confirmation_config.interaction_config.default_value = None;
+        confirmation_config.interaction_config.history_key = None;
+        confirmation_config.interaction_config.complete_cmd = None;
+        confirmation_config.interaction_config.open_editor = false;
 
         let mut confirmation_prompt =
             InteractivePrompt::new(terminal, engine, confirmation_config)?;
@@ -548,6 +1248,10 @@ impl InteractivePrompt {
     }
 
     fn find_choice_validator(&self) -> Option<ChoiceConfig> {
+        // Free-text prompts are typed (with Tab completion) instead
+        if self.config.interaction_config.free_text {
+            return None;
+        }
         for rule_config in &self.config.validation_rules {
             if let ValidatorType::Choices(choices) = &rule_config.validator_type {
                 let min_choices = rule_config
@@ -574,8 +1278,20 @@ impl InteractivePrompt {
                         Vec::new()
                     };
 
+                let ranked = rule_config
+                    .parameters
+                    .get("ranked")
+                    .and_then(|s| s.parse::<bool>().ok())
+                    .unwrap_or(false);
+
+                // Limits were checked when the rule was loaded
+                let groups =
+                    ChoiceGroup::from_choices(choices, &rule_config.parameters).unwrap_or_default();
+
                 return Some(ChoiceConfig {
                     choices: choices.clone(),
+                    groups,
+                    ranked,
                     allow_multiple: max_choices > 1,
                     min_choices,
                     max_choices,
@@ -591,7 +1307,7 @@ impl InteractivePrompt {
         &self,
         default_value: &str,
         selection_separator: &str,
-        available_choices: &[String],
+        available_choices: &[ChoiceOption],
     ) -> Vec<String> {
         // Parse the default value using the same separator as selections
         let default_choices: Vec<String> = default_value
@@ -606,7 +1322,7 @@ impl InteractivePrompt {
             .filter(|choice| {
                 available_choices
                     .iter()
-                    .any(|available| available == choice)
+                    .any(|available| &available.value == choice)
             })
             .collect()
     }
@@ -633,7 +1349,11 @@ impl InteractivePrompt {
             self.config.ui_config.no_color,
             timeout,
             choice_config.default_selections,
-        )?;
+        )?
+        .with_back_navigation(self.config.interaction_config.allow_back)
+        .with_menu_height(self.config.ui_config.menu_height)
+        .with_groups(choice_config.groups)
+        .with_ranking(choice_config.ranked);
 
         let selected_choices = choice_menu.show(prompt_text)?;
 
@@ -647,7 +1367,9 @@ impl InteractivePrompt {
 
 #[derive(Debug)]
 struct ChoiceConfig {
-    choices: Vec<String>,
+    choices: Vec<ChoiceOption>,
+    groups: Vec<ChoiceGroup>,
+    ranked: bool,
     allow_multiple: bool,
     min_choices: usize,
     max_choices: usize,
@@ -668,5 +1390,7 @@ impl Drop for InteractivePrompt {
 enum InputAction {
     Continue,
     Submit,
+    OpenEditor,
     Cancel,
+    Back,
 }
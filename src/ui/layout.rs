@@ -1,11 +1,13 @@
 use super::colors::{ColoredText, Colorizer};
 use crate::validation::{Priority, ValidationResult};
 use crossterm::{
-    cursor::{MoveTo, MoveToColumn, MoveToNextLine, RestorePosition, SavePosition},
+    cursor::{MoveTo, MoveToColumn, MoveToNextLine, MoveUp, RestorePosition, SavePosition},
     terminal::{Clear, ClearType},
     ExecutableCommand,
 };
 use std::io::{self, Write};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug)]
@@ -32,6 +34,11 @@ impl LayoutManager {
         }
     }
 
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+    }
+
     pub fn calculate_layout(&mut self, has_help: bool) {
         // New layout: prompt line 0, errors start at line 1, help text after errors (when errors exist)
         self.prompt_line = 0;
@@ -99,11 +106,106 @@ impl LayoutManager {
     }
 }
 
+/// Split one line of input into rows of at most `width` columns. Also returns
+/// the (row, column) of every grapheme cluster boundary; a cursor after a
+/// full row lands at the start of an extra empty row.
+pub fn wrap_input_line(line: &str, width: usize) -> (Vec<String>, Vec<(usize, usize)>) {
+    let width = width.max(1);
+    let mut rows = vec![String::new()];
+    let mut positions = Vec::with_capacity(line.len() + 1);
+    let mut x = 0;
+    for cluster in line.graphemes(true) {
+        let cluster_width = cluster.width();
+        if x > 0 && x + cluster_width > width {
+            rows.push(String::new());
+            x = 0;
+        }
+        positions.push((rows.len() - 1, x));
+        rows.last_mut().unwrap().push_str(cluster);
+        x += cluster_width;
+    }
+    if x >= width {
+        rows.push(String::new());
+        x = 0;
+    }
+    positions.push((rows.len() - 1, x));
+    (rows, positions)
+}
+
+/// Marks the edges of an input line that is scrolled sideways
+pub const SCROLL_MARKER: &str = "…";
+
+/// The part of a single-line input that fits on the screen
+#[derive(Debug, PartialEq)]
+pub struct InputView {
+    pub first: usize,          // First visible grapheme cluster
+    pub visible: Range<usize>, // Byte range of the visible text
+    pub clipped_left: bool,    // Text is hidden before the visible part
+    pub clipped_right: bool,   // Text is hidden after the visible part
+    pub cursor_x: usize,       // Cursor column, counting the left marker
+}
+
+/// Scroll a single line of input sideways to fit in `width` columns while
+/// keeping the cursor (a grapheme cluster index) in view. `first` is the
+/// first visible cluster of the previous view, so the text only moves when
+/// the cursor would leave it. Hidden text is replaced by [`SCROLL_MARKER`].
+pub fn scroll_input(input: &str, cursor: usize, first: usize, width: usize) -> InputView {
+    let clusters: Vec<(usize, &str)> = input.grapheme_indices(true).collect();
+    let widths: Vec<usize> = clusters.iter().map(|(_, g)| g.width()).collect();
+    let count = clusters.len();
+    let cursor = cursor.min(count);
+    let width = width.max(3);
+    let marker = |first: usize| usize::from(first > 0);
+
+    // Columns needed up to and including the cell under the cursor, with a
+    // marker after it when more text follows
+    let needed = |first: usize| {
+        let cell = widths.get(cursor).copied().unwrap_or(1);
+        let more = usize::from(cursor + 1 < count);
+        marker(first) + widths[first..cursor].iter().sum::<usize>() + cell + more
+    };
+    let mut first = first.min(cursor);
+    while first < cursor && needed(first) > width {
+        first += 1;
+    }
+    // Scroll back once the text up to the end fits again, e.g. after deleting
+    while first > 0 && marker(first - 1) + widths[first - 1..].iter().sum::<usize>() < width {
+        first -= 1;
+    }
+
+    let offset = |index: usize| clusters.get(index).map_or(input.len(), |&(i, _)| i);
+    let left = marker(first);
+    let rest: usize = widths[first..].iter().sum();
+    let last = if left + rest + usize::from(cursor == count) <= width {
+        count
+    } else {
+        // Leave a column for the right marker
+        let mut used = left + 1;
+        let mut last = first;
+        while last < count && used + widths[last] <= width {
+            used += widths[last];
+            last += 1;
+        }
+        last
+    };
+
+    InputView {
+        first,
+        visible: offset(first)..offset(last),
+        clipped_left: first > 0,
+        clipped_right: last < count,
+        cursor_x: left + widths[first..cursor].iter().sum::<usize>(),
+    }
+}
+
 pub struct Screen<W: Write + ExecutableCommand> {
     writer: W,
     layout: LayoutManager,
     colorizer: Colorizer,
     cursor_saved: bool,
+    input_text: String, // Input last written by write_input
+    input_first: usize, // First cluster of the input shown, when scrolled
+    input_column: u16,  // Column the cursor was last placed at on the input line
 }
 
 impl<W: Write + ExecutableCommand> Screen<W> {
@@ -113,6 +215,9 @@ impl<W: Write + ExecutableCommand> Screen<W> {
             layout,
             colorizer,
             cursor_saved: false,
+            input_text: String::new(),
+            input_first: 0,
+            input_column: 0,
         }
     }
 
@@ -158,7 +263,7 @@ impl<W: Write + ExecutableCommand> Screen<W> {
             .write_colored(&mut self.writer, &colored_prompt)?;
 
         // Return the width of the prompt for cursor positioning
-        Ok((prompt_text.len() + 1) as u16)
+        Ok((prompt_text.width() + 1) as u16)
     }
 
     pub fn write_prompt_at(&mut self, prompt_text: &str, x: u16, y: u16) -> io::Result<u16> {
@@ -167,23 +272,36 @@ impl<W: Write + ExecutableCommand> Screen<W> {
         self.write_at(x, y, &colored_prompt)?;
 
         // Return the width of the prompt for cursor positioning
-        Ok((prompt_text.len() + 1) as u16)
+        Ok((prompt_text.width() + 1) as u16)
     }
 
+    /// Write the input after the prompt, scrolled sideways when it's too
+    /// long for the line so that the cursor (a grapheme cluster index) stays
+    /// visible. Returns the column the cursor belongs in.
     pub fn write_input(
         &mut self,
         input: &str,
+        cursor_pos: usize,
         prompt_width: u16,
         error_pos: Option<usize>,
-    ) -> io::Result<()> {
+    ) -> io::Result<u16> {
+        let view = self.input_view(input, cursor_pos, prompt_width);
+        self.input_text = input.to_string();
+        self.input_first = view.first;
+
         // Clear from current position to end of line, then write input
         self.writer
             .execute(crossterm::cursor::MoveToColumn(prompt_width))?;
         self.clear_from_cursor()?;
 
+        if view.clipped_left {
+            self.write_hint(self.colorizer.symbol(SCROLL_MARKER, "<"))?;
+        }
+        let input = &input[view.visible.clone()];
         if let Some(error_pos) = error_pos {
             // Split input at error position and color accordingly
-            let (valid_part, invalid_part) = input.split_at(error_pos.min(input.len()));
+            let error_pos = error_pos.clamp(view.visible.start, view.visible.end);
+            let (valid_part, invalid_part) = input.split_at(error_pos - view.visible.start);
 
             if !valid_part.is_empty() {
                 let valid_text = self.colorizer.valid_text(valid_part);
@@ -202,10 +320,119 @@ impl<W: Write + ExecutableCommand> Screen<W> {
             self.colorizer
                 .write_colored(&mut self.writer, &valid_text)?;
         }
+        if view.clipped_right {
+            self.write_hint(self.colorizer.symbol(SCROLL_MARKER, ">"))?;
+        }
+
+        Ok(prompt_width + view.cursor_x as u16)
+    }
+
+    fn input_view(&self, input: &str, cursor_pos: usize, prompt_width: u16) -> InputView {
+        let width = self.layout.width.saturating_sub(prompt_width + 1) as usize;
+        scroll_input(input, cursor_pos, self.input_first, width)
+    }
 
+    /// Write a dimmed suggestion after the input starting at `column`, cut
+    /// off at the edge of the terminal
+    pub fn write_suggestion(&mut self, suggestion: &str, column: u16) -> io::Result<()> {
+        let mut room = self.layout.width.saturating_sub(column + 1) as usize;
+        let visible: String = suggestion
+            .graphemes(true)
+            .take_while(|cluster| {
+                let width = cluster.width();
+                let fits = width <= room;
+                room = room.saturating_sub(width);
+                fits
+            })
+            .collect();
+        if !visible.is_empty() {
+            let ghost = self.colorizer.help_text(visible);
+            self.colorizer.write_colored(&mut self.writer, &ghost)?;
+        }
         Ok(())
     }
 
+    /// Write dimmed text at the cursor, such as a hint after the prompt
+    pub fn write_hint(&mut self, hint: &str) -> io::Result<()> {
+        let colored_hint = self.colorizer.help_text(hint);
+        self.colorizer
+            .write_colored(&mut self.writer, &colored_hint)?;
+        Ok(())
+    }
+
+    /// Redraw a multi-line text area on the rows below the prompt line,
+    /// wrapped at the terminal width and followed by any errors and help.
+    /// `cursor_row` is the row below the prompt line the cursor is on now,
+    /// as returned by the previous call; the cursor is left at `cursor`
+    /// (line, grapheme cluster) in the text and its new row is returned. Rows are
+    /// ended with newlines rather than cursor moves so the terminal scrolls
+    /// as the text grows.
+    pub fn write_text_area(
+        &mut self,
+        lines: &[String],
+        cursor: (usize, usize),
+        cursor_row: u16,
+        errors: &[ValidationResult],
+        help_text: Option<&str>,
+    ) -> io::Result<u16> {
+        if cursor_row > 0 {
+            self.writer.execute(MoveUp(cursor_row))?;
+        }
+
+        let width = self.layout.width as usize;
+        let mut row = 0u16;
+        let mut target = (0u16, 0u16);
+        for (index, line) in lines.iter().enumerate() {
+            let (rows, positions) = wrap_input_line(line, width);
+            if index == cursor.0 {
+                let (cursor_row, cursor_x) = positions[cursor.1.min(positions.len() - 1)];
+                target = (row + 1 + cursor_row as u16, cursor_x as u16);
+            }
+            for text in rows {
+                write!(self.writer, "\r\n")?;
+                if row == 0 {
+                    // Clear the previous text, errors and help below the prompt
+                    self.clear_from_cursor()?;
+                }
+                let colored_row = self.colorizer.valid_text(text);
+                self.colorizer
+                    .write_colored(&mut self.writer, &colored_row)?;
+                row += 1;
+            }
+        }
+
+        let mut footer: Vec<ColoredText> = errors
+            .iter()
+            .take(10)
+            .filter_map(|error| {
+                let message = error.message.as_ref()?;
+                Some(match error.priority {
+                    Priority::Critical | Priority::High => self.colorizer.error_message(message),
+                    Priority::Medium => self.colorizer.warning_message(message),
+                    Priority::Low => self.colorizer.info_message(message),
+                })
+            })
+            .collect();
+        if let (false, Some(help_text)) = (footer.is_empty(), help_text) {
+            footer.push(self.colorizer.help_text(help_text));
+        }
+        for colored_text in footer {
+            for wrapped_line in self.layout.wrap_text(&colored_text.text, self.layout.width) {
+                write!(self.writer, "\r\n")?;
+                let wrapped_colored = ColoredText::new(wrapped_line, colored_text.color);
+                self.colorizer
+                    .write_colored(&mut self.writer, &wrapped_colored)?;
+                row += 1;
+            }
+        }
+
+        if row > target.0 {
+            self.writer.execute(MoveUp(row - target.0))?;
+        }
+        self.writer.execute(MoveToColumn(target.1))?;
+        Ok(target.0)
+    }
+
     pub fn write_errors(&mut self, errors: &[ValidationResult]) -> io::Result<()> {
         // Save cursor position at the start - caller will restore after help text
         self.writer.execute(crossterm::cursor::SavePosition)?;
@@ -246,6 +473,45 @@ impl<W: Write + ExecutableCommand> Screen<W> {
         Ok(())
     }
 
+    /// List completion candidates in columns below the prompt line, using at
+    /// most `max_rows` lines. Like `write_errors`, the cursor position is saved
+    /// for the caller to restore.
+    pub fn write_candidates(&mut self, candidates: &[String], max_rows: u16) -> io::Result<()> {
+        self.writer.execute(SavePosition)?;
+        self.writer.execute(MoveToNextLine(1))?;
+        self.clear_from_cursor()?;
+
+        let column_width = candidates.iter().map(|c| c.width()).max().unwrap_or(0) + 2;
+        let columns = (self.layout.width as usize / column_width).max(1);
+        let max_rows = max_rows.max(1) as usize;
+
+        // Keep the last row to say how many candidates did not fit
+        let shown = if candidates.len().div_ceil(columns) > max_rows {
+            (max_rows - 1) * columns
+        } else {
+            candidates.len()
+        };
+        for row in candidates[..shown].chunks(columns) {
+            let line: String = row
+                .iter()
+                .map(|candidate| format!("{:<1$}", candidate, column_width))
+                .collect();
+            let colored_row = self.colorizer.help_text(line.trim_end());
+            self.colorizer
+                .write_colored(&mut self.writer, &colored_row)?;
+            self.writer.execute(MoveToNextLine(1))?;
+        }
+        if shown < candidates.len() {
+            let more = self.colorizer.help_text(format!(
+                "{} {} more",
+                self.colorizer.symbol("…", "..."),
+                candidates.len() - shown
+            ));
+            self.colorizer.write_colored(&mut self.writer, &more)?;
+        }
+        Ok(())
+    }
+
     pub fn write_help(&mut self, help_text: &str) -> io::Result<()> {
         if self.layout.help_line.is_some() {
             // Help text is written at the current cursor position (after errors)
@@ -278,20 +544,40 @@ impl<W: Write + ExecutableCommand> Screen<W> {
         Ok(())
     }
 
+    /// Move to the cursor position in the input last written, scrolling
+    /// the input first if the cursor has moved out of view
     pub fn position_cursor_at_input_pos(
         &mut self,
-        input: &str,
         cursor_pos: usize,
         prompt_width: u16,
     ) -> io::Result<()> {
-        let chars: Vec<char> = input.chars().collect();
-        let text_before_cursor: String = chars.iter().take(cursor_pos).collect();
-        let cursor_x = prompt_width + text_before_cursor.width() as u16;
+        let view = self.input_view(&self.input_text, cursor_pos, prompt_width);
+        let cursor_x = if view.first == self.input_first {
+            prompt_width + view.cursor_x as u16
+        } else {
+            let input = std::mem::take(&mut self.input_text);
+            self.write_input(&input, cursor_pos, prompt_width, None)?
+        };
         // Move to the correct column on the current line
         self.writer.execute(MoveToColumn(cursor_x))?;
+        self.input_column = cursor_x;
         Ok(())
     }
 
+    /// Take on a new terminal size and clear from the start of the prompt
+    /// line down, ready for a redraw. Terminals that re-wrap long lines when
+    /// they narrow, as most do, may have carried the cursor down onto a
+    /// continuation of the prompt line, so it is moved back up first.
+    pub fn resize(&mut self, width: u16, height: u16) -> io::Result<()> {
+        let rows_down = self.input_column / width.max(1);
+        if rows_down > 0 {
+            self.writer.execute(MoveUp(rows_down))?;
+        }
+        self.layout.resize(width, height);
+        self.writer.execute(MoveToColumn(0))?;
+        self.clear_from_cursor()
+    }
+
     pub fn write_choice(&mut self, choice_text: &str) -> io::Result<()> {
         let colored_choice = self.colorizer.valid_text(choice_text);
         self.colorizer
@@ -316,3 +602,91 @@ impl<W: Write + ExecutableCommand> Screen<W> {
         &mut self.layout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::colors::ColorScheme;
+
+    #[test]
+    fn test_wrap_input_line() {
+        let (rows, positions) = wrap_input_line("abcdef", 4);
+        assert_eq!(rows, ["abcd", "ef"]);
+        assert_eq!(positions[4], (1, 0));
+        assert_eq!(positions[6], (1, 2));
+
+        // A full last row leaves the cursor at the start of an empty one
+        let (rows, positions) = wrap_input_line("abcd", 4);
+        assert_eq!(rows, ["abcd", ""]);
+        assert_eq!(positions[4], (1, 0));
+
+        // Wide characters move to the next row rather than being split
+        let (rows, positions) = wrap_input_line("ab日本", 5);
+        assert_eq!(rows, ["ab日", "本"]);
+        assert_eq!(positions[3], (1, 0));
+        assert_eq!(wrap_input_line("", 4).0, [""]);
+
+        // Clusters are never split: the ZWJ family emoji is one position
+        let (rows, positions) = wrap_input_line("e\u{301}👨\u{200d}👩\u{200d}👧x", 3);
+        assert_eq!(rows, ["e\u{301}👨\u{200d}👩\u{200d}👧", "x"]);
+        assert_eq!(positions, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_cursor_column_in_mixed_input() {
+        let input = "a日e\u{301}👍🏽b";
+        let cursor_x = |cursor| scroll_input(input, cursor, 0, 80).cursor_x;
+        assert_eq!(cursor_x(0), 0);
+        assert_eq!(cursor_x(2), 3);
+        assert_eq!(cursor_x(3), 4);
+        assert_eq!(cursor_x(4), 6);
+        assert_eq!(cursor_x(10), 7);
+    }
+
+    #[test]
+    fn test_prompt_width_in_columns() {
+        let colorizer = Colorizer::new(ColorScheme::no_color(), true);
+        let mut screen = Screen::new(Vec::new(), LayoutManager::new(80, 24), colorizer);
+        assert_eq!(screen.write_prompt("Név 日本:").unwrap(), 10);
+        assert_eq!(screen.write_prompt_at("✓ ok:", 0, 0).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_scroll_input() {
+        let url = "https://example.com/path";
+        let shown = |view: &InputView| &url[view.visible.clone()];
+
+        // Fits: nothing is clipped
+        let view = scroll_input(url, 24, 0, 30);
+        assert_eq!(
+            (shown(&view), view.clipped_left, view.cursor_x),
+            (url, false, 24)
+        );
+
+        // At the end, the start scrolls off behind a marker
+        let view = scroll_input(url, 24, 0, 10);
+        assert_eq!(shown(&view), "com/path");
+        assert!(view.clipped_left && !view.clipped_right);
+        assert_eq!(view.cursor_x, 9);
+
+        // Moving left keeps the view until the cursor reaches its edge
+        let moved = scroll_input(url, 18, view.first, 10);
+        assert_eq!(moved.first, view.first);
+        let moved = scroll_input(url, 10, view.first, 10);
+        assert_eq!((moved.first, moved.cursor_x), (10, 1));
+        assert_eq!(shown(&moved), "ample.co");
+        assert!(moved.clipped_right);
+
+        // At the start, only the end is clipped
+        let view = scroll_input(url, 0, moved.first, 10);
+        assert_eq!(
+            (shown(&view), view.clipped_left, view.clipped_right),
+            ("https://e", false, true)
+        );
+
+        // Wide characters are never split at the edges
+        let view = scroll_input("日本語のテキスト", 8, 0, 9);
+        assert_eq!(view.visible, "日本語の".len() + "テ".len()..24);
+        assert_eq!(view.cursor_x, 7);
+    }
+}
@@ -1,13 +1,55 @@
 use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
     tty::IsTty,
     ExecutableCommand,
 };
-use std::io::{self, stdout, Stdout};
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+/// The controlling terminal, opened once. Keys are read from it by crossterm
+/// and the UI is drawn on it, so askr stays interactive when stdin, stdout
+/// or stderr is redirected, as in `cat list | askr --choices-file - | sort`.
+fn controlling_terminal() -> Option<&'static File> {
+    static TTY: OnceLock<Option<File>> = OnceLock::new();
+    TTY.get_or_init(|| {
+        if cfg!(unix) {
+            File::options().read(true).write(true).open("/dev/tty").ok()
+        } else {
+            None
+        }
+    })
+    .as_ref()
+}
+
+/// Writer for the interactive UI: the controlling terminal, or stderr where
+/// it can't be opened (e.g. on Windows)
+pub struct Tty;
+
+pub fn tty() -> Tty {
+    Tty
+}
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match controlling_terminal() {
+            Some(mut file) => file.write(buf),
+            None => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match controlling_terminal() {
+            Some(mut file) => file.flush(),
+            None => io::stderr().flush(),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct TerminalCapabilities {
-    pub colors_supported: bool,
+    pub colors: ColorDepth,
     pub cursor_control: bool,
     pub unicode_support: bool,
     pub width: u16,
@@ -15,36 +57,80 @@ pub struct TerminalCapabilities {
     pub supports_alternate_screen: bool,
 }
 
+/// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    None,
+    Basic,     // The 16 ANSI colors
+    Ansi256,   // TERM=*-256color
+    TrueColor, // COLORTERM=truecolor or 24bit
+}
+
 impl TerminalCapabilities {
     pub fn detect() -> io::Result<Self> {
-        // Check if we're in a TTY
-        if !io::stdout().is_tty() {
-            return Ok(Self {
-                colors_supported: false,
-                cursor_control: false,
-                unicode_support: true,
-                width: 80,  // Default width
-                height: 24, // Default height
-                supports_alternate_screen: false,
-            });
+        // Interaction needs a terminal to read keys from and draw on; where
+        // stdout or stdin is redirected the controlling terminal is used
+        let has_terminal = if cfg!(unix) {
+            controlling_terminal().is_some()
+        } else {
+            io::stderr().is_tty()
+        };
+        let mut capabilities = Self::from_env(|name| std::env::var(name).ok());
+        if !has_terminal || !capabilities.cursor_control {
+            capabilities.cursor_control = false;
+            return Ok(capabilities);
         }
 
-        let (width, height) = terminal::size()?;
+        (capabilities.width, capabilities.height) = terminal::size()?;
+        Ok(capabilities)
+    }
 
-        Ok(Self {
-            colors_supported: true, // Most modern terminals support colors
-            cursor_control: true,   // crossterm handles this
-            unicode_support: true,  // Assume unicode support
-            width,
-            height,
+    /// Capabilities described by the environment: `TERM`, `COLORTERM`,
+    /// `NO_COLOR`, `CLICOLOR_FORCE` and the locale. `var` looks up a variable.
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let term = var("TERM").unwrap_or_default();
+        // Windows consoles don't set TERM
+        let dumb = term == "dumb" || (term.is_empty() && !cfg!(windows));
+
+        let colors = if var("NO_COLOR").is_some() {
+            ColorDepth::None
+        } else if dumb {
+            match var("CLICOLOR_FORCE") {
+                Some(force) if force != "0" => ColorDepth::Basic,
+                _ => ColorDepth::None,
+            }
+        } else if matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit")) {
+            ColorDepth::TrueColor
+        } else if term.ends_with("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Basic
+        };
+
+        // The first locale variable set decides the character encoding
+        let unicode_support = match ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().find_map(var) {
+            Some(locale) => {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            None => cfg!(windows),
+        };
+
+        Self {
+            colors,
+            cursor_control: !dumb, // A dumb terminal can't move the cursor
+            unicode_support,
+            width: 80,                        // Default width
+            height: 24,                       // Default height
             supports_alternate_screen: false, // We don't use alternate screen mode
-        })
+        }
     }
 
     pub fn fallback_ui(&self) -> UIMode {
         if !self.cursor_control {
             UIMode::Simple
-        } else if !self.colors_supported {
+        } else if self.colors == ColorDepth::None {
             UIMode::NoColor
         } else {
             UIMode::Full
@@ -52,7 +138,7 @@ impl TerminalCapabilities {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UIMode {
     Full,    // Full UI with colors and cursor control
     NoColor, // No colors but cursor control
@@ -60,9 +146,8 @@ pub enum UIMode {
 }
 
 pub struct Terminal {
-    stdout: Stdout,
     capabilities: TerminalCapabilities,
-    original_hook: Option<Box<dyn Fn() + Send + Sync>>,
+    bracketed_paste: bool, // Pastes arrive as one event rather than as keys
 }
 
 impl Terminal {
@@ -70,32 +155,51 @@ impl Terminal {
         let capabilities = TerminalCapabilities::detect()?;
 
         Ok(Self {
-            stdout: stdout(),
             capabilities,
-            original_hook: None,
+            bracketed_paste: false,
         })
     }
 
+    /// A terminal whose mode is never changed, for testing the state built
+    /// on it without touching the developer's tty
+    #[cfg(test)]
+    pub(crate) fn detached() -> Self {
+        let mut capabilities = TerminalCapabilities::from_env(|_| None);
+        capabilities.cursor_control = false;
+
+        Self {
+            capabilities,
+            bracketed_paste: false,
+        }
+    }
+
+    /// Enter raw mode with bracketed paste, so pasted text is read as one
+    /// `Event::Paste` instead of a stream of keys that could include Enter
     pub fn enter_raw_mode(&mut self) -> io::Result<()> {
         terminal::enable_raw_mode()?;
+        tty().execute(EnableBracketedPaste)?;
+        self.bracketed_paste = true;
         Ok(())
     }
 
     pub fn leave_raw_mode(&mut self) -> io::Result<()> {
+        if std::mem::take(&mut self.bracketed_paste) {
+            tty().execute(DisableBracketedPaste)?;
+        }
         terminal::disable_raw_mode()?;
         Ok(())
     }
 
     pub fn enter_alternate_screen(&mut self) -> io::Result<()> {
         if self.capabilities.supports_alternate_screen {
-            self.stdout.execute(EnterAlternateScreen)?;
+            tty().execute(EnterAlternateScreen)?;
         }
         Ok(())
     }
 
     pub fn leave_alternate_screen(&mut self) -> io::Result<()> {
         if self.capabilities.supports_alternate_screen {
-            self.stdout.execute(LeaveAlternateScreen)?;
+            tty().execute(LeaveAlternateScreen)?;
         }
         Ok(())
     }
@@ -109,14 +213,12 @@ impl Terminal {
     }
 
     pub fn clear_screen(&mut self) -> io::Result<()> {
-        self.stdout
-            .execute(terminal::Clear(terminal::ClearType::All))?;
+        tty().execute(terminal::Clear(terminal::ClearType::All))?;
         Ok(())
     }
 
     pub fn flush(&mut self) -> io::Result<()> {
-        use std::io::Write;
-        self.stdout.flush()
+        tty().flush()
     }
 }
 
@@ -127,3 +229,63 @@ impl Drop for Terminal {
         let _ = self.leave_raw_mode();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities(vars: &[(&str, &str)]) -> TerminalCapabilities {
+        TerminalCapabilities::from_env(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_detect_colors() {
+        let colors = |vars: &[(&str, &str)]| capabilities(vars).colors;
+        assert_eq!(colors(&[("TERM", "xterm")]), ColorDepth::Basic);
+        assert_eq!(colors(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(
+            colors(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            colors(&[("TERM", "xterm"), ("NO_COLOR", "1")]),
+            ColorDepth::None
+        );
+        // An empty NO_COLOR is ignored
+        assert_eq!(
+            colors(&[("TERM", "xterm"), ("NO_COLOR", "")]),
+            ColorDepth::Basic
+        );
+        assert_eq!(colors(&[("TERM", "dumb")]), ColorDepth::None);
+        assert_eq!(
+            colors(&[("TERM", "dumb"), ("CLICOLOR_FORCE", "1")]),
+            ColorDepth::Basic
+        );
+        assert_eq!(
+            colors(&[("TERM", "dumb"), ("CLICOLOR_FORCE", "0")]),
+            ColorDepth::None
+        );
+    }
+
+    #[test]
+    fn test_detect_ui_mode_and_unicode() {
+        let xterm = capabilities(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(xterm.fallback_ui(), UIMode::Full);
+        assert!(xterm.unicode_support);
+
+        let no_color = capabilities(&[("TERM", "xterm"), ("NO_COLOR", "1")]);
+        assert_eq!(no_color.fallback_ui(), UIMode::NoColor);
+
+        let dumb = capabilities(&[("TERM", "dumb"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(dumb.fallback_ui(), UIMode::Simple);
+
+        // LC_ALL overrides LANG
+        let c_locale = capabilities(&[("TERM", "xterm"), ("LANG", "en_US.utf8"), ("LC_ALL", "C")]);
+        assert!(!c_locale.unicode_support);
+        assert!(capabilities(&[("TERM", "xterm"), ("LC_CTYPE", "de_DE.utf8")]).unicode_support);
+    }
+}
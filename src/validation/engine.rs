@@ -1,4 +1,5 @@
 use super::{PartialValidationResult, Priority, ValidationResult, ValidationSummary, Validator};
+use crate::error::Result;
 use dashmap::DashMap;
 use std::time::Instant;
 
@@ -59,6 +60,29 @@ impl ValidationEngine {
         summary
     }
 
+    /// Validate input, failing if any validator could not be run at all
+    pub fn try_validate(&self, input: &str) -> Result<ValidationSummary> {
+        let start_time = Instant::now();
+
+        if let Some(cached) = self.get_cached_results(input) {
+            let mut summary = ValidationSummary::new(input.to_string(), cached);
+            summary.metadata.validation_time_ms = start_time.elapsed().as_millis() as u64;
+            return Ok(summary);
+        }
+
+        let results = self
+            .validators
+            .iter()
+            .map(|validator| validator.try_validate(input))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.cache_results(input, &results);
+
+        let mut summary = ValidationSummary::new(input.to_string(), results);
+        summary.metadata.validation_time_ms = start_time.elapsed().as_millis() as u64;
+        Ok(summary)
+    }
+
     pub fn partial_validate(&self, input: &str, cursor_pos: usize) -> PartialValidationResult {
         // For partial validation, we find the first error position across all validators
         let mut first_error_pos: Option<usize> = None;
@@ -98,13 +122,31 @@ impl ValidationEngine {
         }
     }
 
+    /// Get errors to display while typing; submit-only validators are skipped
     pub fn get_display_errors(
         &self,
         input: &str,
         max_errors: Option<usize>,
     ) -> Vec<ValidationResult> {
-        let summary = self.validate(input);
-        self.filter_display_errors(summary.validation_results, max_errors)
+        let results = if self.validators.iter().any(|v| v.submit_only()) {
+            self.validators
+                .iter()
+                .filter(|validator| !validator.submit_only())
+                .map(|validator| validator.validate(input))
+                .collect()
+        } else {
+            self.validate(input).validation_results
+        };
+        self.filter_display_errors(results, max_errors)
+    }
+
+    /// Get errors to display for an already computed summary (e.g. on submit)
+    pub fn get_summary_display_errors(
+        &self,
+        summary: &ValidationSummary,
+        max_errors: Option<usize>,
+    ) -> Vec<ValidationResult> {
+        self.filter_display_errors(summary.validation_results.clone(), max_errors)
     }
 
     fn filter_display_errors(
@@ -167,7 +209,18 @@ impl ValidationEngine {
         self.cache.as_ref()?.get(input).map(|entry| entry.clone())
     }
 
+    /// Cache results, unless a submit-only validator failed: external
+    /// commands may time out or change their answer, so a resubmission of the
+    /// same input runs them again
     fn cache_results(&self, input: &str, results: &[ValidationResult]) {
+        let submit_only_failed = self
+            .validators
+            .iter()
+            .zip(results)
+            .any(|(validator, result)| validator.submit_only() && !result.passed);
+        if submit_only_failed {
+            return;
+        }
         if let Some(cache) = &self.cache {
             cache.insert(input.to_string(), results.to_vec());
         }
@@ -188,7 +241,7 @@ impl ValidationEngine {
         let mut messages = Vec::new();
 
         // Get default error messages from each validator by testing with invalid input
-        for validator in &self.validators {
+        for validator in self.validators.iter().filter(|v| !v.submit_only()) {
             // Try various test inputs to trigger different error conditions
             let test_inputs = vec![
                 "", // Empty input
@@ -231,6 +284,35 @@ mod tests {
     use crate::validation::rules::basic::{
         MaxLengthValidator, MinLengthValidator, RequiredValidator,
     };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Rejects everything, counting how often it runs
+    #[derive(Default)]
+    struct SubmitOnlyValidator(Arc<AtomicUsize>);
+
+    impl Validator for SubmitOnlyValidator {
+        fn validate(&self, _input: &str) -> ValidationResult {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            ValidationResult::failure("submit_only", Priority::High, "Rejected on submit")
+        }
+
+        fn partial_validate(&self, _input: &str, _cursor_pos: usize) -> PartialValidationResult {
+            PartialValidationResult::valid()
+        }
+
+        fn priority(&self) -> Priority {
+            Priority::High
+        }
+
+        fn name(&self) -> &str {
+            "submit_only"
+        }
+
+        fn submit_only(&self) -> bool {
+            true
+        }
+    }
 
     #[test]
     fn test_empty_validation_engine() {
@@ -356,6 +438,28 @@ mod tests {
         assert!(messages.iter().any(|msg| msg.contains("Minimum length")));
     }
 
+    #[test]
+    fn test_submit_only_validators_skipped_while_typing() {
+        let mut engine = ValidationEngine::new();
+        engine.add_validator(Box::new(SubmitOnlyValidator::default()));
+        engine.add_validator(Box::new(MinLengthValidator::new(5)));
+
+        let errors = engine.get_display_errors("hi", None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].rule_name, "min_length");
+
+        let summary = engine.validate("hello");
+        assert!(!summary.valid);
+        let errors = engine.get_summary_display_errors(&summary, None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].rule_name, "submit_only");
+
+        assert!(engine
+            .get_potential_error_messages()
+            .iter()
+            .all(|msg| !msg.contains("Rejected on submit")));
+    }
+
     #[test]
     fn test_validation_engine_without_cache() {
         let engine = ValidationEngine::without_cache();
@@ -389,6 +493,19 @@ mod tests {
         assert!(time2 <= time1 + 1); // Allow for timing variations
     }
 
+    #[test]
+    fn test_submit_only_failures_not_cached() {
+        let validator = SubmitOnlyValidator::default();
+        let calls = Arc::clone(&validator.0);
+        let mut engine = ValidationEngine::new();
+        engine.add_validator(Box::new(validator));
+
+        // A rejected submission runs the validator again when resubmitted
+        assert!(!engine.try_validate("hello").unwrap().valid);
+        assert!(!engine.try_validate("hello").unwrap().valid);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
     #[test]
     fn test_cache_invalidation() {
         let mut engine = ValidationEngine::new();
@@ -7,11 +7,23 @@ pub use engine::ValidationEngine;
 pub use priority::Priority;
 pub use result::{PartialValidationResult, ValidationResult, ValidationSummary};
 
+use serde::de::{
+    self, value::MapAccessDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+
 /// Core trait for all validators
 pub trait Validator: Send + Sync {
     /// Validate complete input
     fn validate(&self, input: &str) -> ValidationResult;
 
+    /// Validate complete input, reporting validators that could not run at all
+    fn try_validate(&self, input: &str) -> crate::error::Result<ValidationResult> {
+        Ok(self.validate(input))
+    }
+
     /// Validate partial input during typing
     fn partial_validate(&self, input: &str, cursor_pos: usize) -> PartialValidationResult;
 
@@ -25,6 +37,12 @@ pub trait Validator: Send + Sync {
     fn description(&self) -> &str {
         self.name()
     }
+
+    /// Whether this validator should only run when input is submitted,
+    /// rather than on every keystroke
+    fn submit_only(&self) -> bool {
+        false
+    }
 }
 
 /// Configuration for a validation rule
@@ -33,10 +51,331 @@ pub struct ValidationRuleConfig {
     pub validator_type: ValidatorType,
     pub priority: Option<Priority>,
     pub custom_message: Option<String>,
-    pub parameters: std::collections::HashMap<String, String>,
+    pub parameters: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone)]
+/// Every `type` a rule can have, in the order `ValidatorType` declares them
+const RULE_TYPES: &[&str] = &[
+    "required",
+    "min_length",
+    "max_length",
+    "pattern",
+    "email",
+    "hostname",
+    "url",
+    "ipv4",
+    "ipv6",
+    "integer",
+    "float",
+    "range",
+    "positive",
+    "negative",
+    "date",
+    "time",
+    "datetime",
+    "choices",
+    "file_exists",
+    "dir_exists",
+    "path_exists",
+    "readable",
+    "writable",
+    "executable",
+    "command",
+];
+
+/// Keys of a rule table
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum RuleField {
+    Type,
+    Value,
+    Priority,
+    Message,
+    Parameters,
+}
+
+#[derive(Deserialize)]
+struct Parameters(#[serde(deserialize_with = "deserialize_parameters")] HashMap<String, String>);
+
+/// A rule's `type`, rejected as soon as it is read when unknown
+struct RuleTypeSeed;
+
+impl<'de> DeserializeSeed<'de> for RuleTypeSeed {
+    type Value = String;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<String, D::Error> {
+        let kind = String::deserialize(deserializer)?;
+        if !RULE_TYPES.contains(&kind.as_str()) {
+            return Err(de::Error::unknown_variant(&kind, RULE_TYPES));
+        }
+        Ok(kind)
+    }
+}
+
+/// A rule's `type` and the parser's own deserializer for its `value`,
+/// presented as the map `ValidatorType` expects
+struct TypedValue<'a, A> {
+    kind: &'a str,
+    value: Option<&'a mut A>,
+    entries_read: usize,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for TypedValue<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let key = match self.entries_read {
+            0 => "type",
+            1 if self.value.is_some() => "value",
+            _ => return Ok(None),
+        };
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.entries_read += 1;
+        match self.value.as_mut() {
+            Some(map) if self.entries_read == 2 => map.next_value_seed(seed),
+            _ => seed.deserialize(self.kind.into_deserializer()),
+        }
+    }
+}
+
+/// Rules are read key by key, without `#[serde(flatten)]`, so the parser
+/// reports an error at the offending key rather than at the start of the
+/// rule's table
+impl<'de> Deserialize<'de> for ValidationRuleConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RuleVisitor;
+
+        impl<'de> Visitor<'de> for RuleVisitor {
+            type Value = ValidationRuleConfig;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a rule table with a type")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<ValidationRuleConfig, A::Error> {
+                let mut kind: Option<String> = None;
+                let mut validator_type = None;
+                // A value given before the type is kept until the type is known
+                let mut early_value: Option<serde_json::Value> = None;
+                let mut priority = None;
+                let mut custom_message = None;
+                let mut parameters = None;
+
+                while let Some(field) = map.next_key()? {
+                    match field {
+                        RuleField::Type if kind.is_some() => {
+                            return Err(de::Error::duplicate_field("type"))
+                        }
+                        RuleField::Type => kind = Some(map.next_value_seed(RuleTypeSeed)?),
+                        RuleField::Value if validator_type.is_some() || early_value.is_some() => {
+                            return Err(de::Error::duplicate_field("value"))
+                        }
+                        RuleField::Value => match &kind {
+                            Some(kind) => {
+                                let value = TypedValue {
+                                    kind,
+                                    value: Some(&mut map),
+                                    entries_read: 0,
+                                };
+                                validator_type = Some(ValidatorType::deserialize(
+                                    MapAccessDeserializer::new(value),
+                                )?);
+                            }
+                            None => early_value = Some(map.next_value()?),
+                        },
+                        RuleField::Priority if priority.is_some() => {
+                            return Err(de::Error::duplicate_field("priority"))
+                        }
+                        RuleField::Priority => priority = Some(map.next_value()?),
+                        RuleField::Message if custom_message.is_some() => {
+                            return Err(de::Error::duplicate_field("message"))
+                        }
+                        RuleField::Message => custom_message = Some(map.next_value()?),
+                        RuleField::Parameters if parameters.is_some() => {
+                            return Err(de::Error::duplicate_field("parameters"))
+                        }
+                        RuleField::Parameters => {
+                            parameters = Some(map.next_value::<Parameters>()?.0)
+                        }
+                    }
+                }
+
+                let kind = kind.ok_or_else(|| de::Error::missing_field("type"))?;
+                let validator_type = match (validator_type, early_value) {
+                    (Some(validator_type), _) => validator_type,
+                    (None, Some(value)) => ValidatorType::deserialize(
+                        serde_json::json!({ "type": kind, "value": value }),
+                    )
+                    .map_err(de::Error::custom)?,
+                    (None, None) => {
+                        ValidatorType::deserialize(MapAccessDeserializer::new(TypedValue::<A> {
+                            kind: &kind,
+                            value: None,
+                            entries_read: 0,
+                        }))?
+                    }
+                };
+
+                Ok(ValidationRuleConfig {
+                    validator_type,
+                    priority: priority.flatten(),
+                    custom_message: custom_message.flatten(),
+                    parameters: parameters.unwrap_or_default(),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(RuleVisitor)
+    }
+}
+
+/// Accept scalar parameter values of any type (e.g. `min_choices = 2`) and
+/// store them as strings, matching how CLI-derived parameters are kept
+fn deserialize_parameters<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    raw.into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => Ok((key, s)),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+                Ok((key, value.to_string()))
+            }
+            _ => Err(serde::de::Error::custom(format!(
+                "parameter '{}' must be a string, number or boolean",
+                key
+            ))),
+        })
+        .collect()
+}
+
+/// A selectable choice: the value that is validated and output, with an
+/// optional label and description shown in menus instead, and the group it
+/// is listed under. A choice can be disabled (shown but never selectable) or
+/// locked (always selected).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceOption {
+    pub value: String,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub group: Option<String>,
+    /// Why the choice is unavailable; empty when disabled without a reason
+    pub disabled: Option<String>,
+    pub locked: bool,
+}
+
+impl ChoiceOption {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            label: None,
+            description: None,
+            group: None,
+            disabled: None,
+            locked: false,
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// Text shown in menus: the label, or the value when there is none
+    pub fn display_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.value)
+    }
+}
+
+/// Table form of a choice in rules files and JSON choice lists
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetailedChoice {
+    value: String,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_disabled")]
+    disabled: Option<String>,
+    #[serde(default)]
+    locked: bool,
+}
+
+/// `disabled` is either a flag or the reason the choice is unavailable
+fn deserialize_disabled<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Bool(true) => Ok(Some(String::new())),
+        serde_json::Value::Bool(false) => Ok(None),
+        serde_json::Value::String(reason) => Ok(Some(reason)),
+        _ => Err(de::Error::custom(
+            "disabled must be true, false or a reason",
+        )),
+    }
+}
+
+/// Choices are written either as plain values or as tables with a value and
+/// optional label, description, group, disabled and locked keys
+impl<'de> Deserialize<'de> for ChoiceOption {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ChoiceOptionVisitor;
+
+        impl<'de> Visitor<'de> for ChoiceOptionVisitor {
+            type Value = ChoiceOption;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a choice value or a table with a value")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ChoiceOption, E> {
+                Ok(ChoiceOption::new(value))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<ChoiceOption, A::Error> {
+                let choice = DetailedChoice::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(ChoiceOption {
+                    value: choice.value,
+                    label: choice.label,
+                    description: choice.description,
+                    group: choice.group,
+                    disabled: choice.disabled,
+                    locked: choice.locked,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(ChoiceOptionVisitor)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum ValidatorType {
     Required,
     MinLength(usize),
@@ -54,12 +393,17 @@ pub enum ValidatorType {
     Negative,
     Date(Option<String>),
     Time(Option<String>),
+    #[serde(rename = "datetime")]
     DateTime(Option<String>),
-    Choices(Vec<String>),
+    Choices(Vec<ChoiceOption>),
     FileExists,
     DirExists,
     PathExists,
     Readable,
     Writable,
     Executable,
+    Command(String),
+    /// A form rule that earlier answers made unusable, rejecting all input
+    #[serde(skip)]
+    Unresolved(String),
 }
@@ -37,6 +37,15 @@ impl Priority {
             Priority::Low => "💡",
         }
     }
+
+    /// Stand-in for [`icon`](Self::icon) on terminals that can't show emoji
+    pub fn ascii_icon(&self) -> &'static str {
+        match self {
+            Priority::Critical | Priority::High => "[ERROR]",
+            Priority::Medium => "[WARN]",
+            Priority::Low => "[INFO]",
+        }
+    }
 }
 
 impl Default for Priority {
@@ -53,6 +53,40 @@ impl Validator for RequiredValidator {
     }
 }
 
+/// Validator standing in for a form rule that could not be built from
+/// earlier answers. It rejects all input with the reason, so the user can go
+/// back and change those answers.
+#[derive(Debug)]
+pub struct UnresolvedRuleValidator {
+    message: String,
+}
+
+impl UnresolvedRuleValidator {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Validator for UnresolvedRuleValidator {
+    fn validate(&self, _input: &str) -> ValidationResult {
+        ValidationResult::failure("unresolved_rule", Priority::Critical, &self.message)
+    }
+
+    fn partial_validate(&self, _input: &str, _cursor_pos: usize) -> PartialValidationResult {
+        PartialValidationResult::valid()
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::Critical
+    }
+
+    fn name(&self) -> &str {
+        "unresolved_rule"
+    }
+}
+
 /// Validator for minimum length
 #[derive(Debug)]
 pub struct MinLengthValidator {
@@ -1,5 +1,5 @@
-use super::super::{PartialValidationResult, Priority, ValidationResult, Validator};
-use std::collections::HashSet;
+use super::super::{ChoiceOption, PartialValidationResult, Priority, ValidationResult, Validator};
+use std::collections::{HashMap, HashSet};
 
 /// Choice validator for selecting from predefined options
 #[derive(Debug)]
@@ -11,8 +11,101 @@ pub struct ChoiceValidator {
     priority: Priority,
     custom_message: Option<String>,
     selection_separator: String,
+    suggestions: bool,
+    groups: Vec<ChoiceGroup>,
+    disabled: Vec<(String, String)>,
+    locked: Vec<String>,
 }
 
+/// A named group of choices with optional limits on how many of its members
+/// may be selected
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceGroup {
+    pub name: String,
+    pub members: Vec<String>,
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl ChoiceGroup {
+    /// Collect the groups of `choices` in order of first appearance, reading
+    /// per-group limits from `min_choices:<group>` / `max_choices:<group>`
+    /// parameters
+    pub fn from_choices(
+        choices: &[ChoiceOption],
+        parameters: &HashMap<String, String>,
+    ) -> Result<Vec<ChoiceGroup>, String> {
+        let mut groups: Vec<ChoiceGroup> = Vec::new();
+        for choice in choices {
+            let Some(name) = &choice.group else {
+                continue;
+            };
+            match groups.iter_mut().find(|group| &group.name == name) {
+                Some(group) => group.members.push(choice.value.clone()),
+                None => groups.push(ChoiceGroup {
+                    name: name.clone(),
+                    members: vec![choice.value.clone()],
+                    min: None,
+                    max: None,
+                }),
+            }
+        }
+
+        let mut keys: Vec<&String> = parameters.keys().collect();
+        keys.sort();
+        for key in keys {
+            let Some((limit, name)) = key.split_once(':') else {
+                continue;
+            };
+            if limit != "min_choices" && limit != "max_choices" {
+                continue;
+            }
+            let group = groups
+                .iter_mut()
+                .find(|group| group.name == name)
+                .ok_or_else(|| format!("'{}' refers to unknown choice group '{}'", key, name))?;
+            let value = parameters[key]
+                .parse::<usize>()
+                .map_err(|_| format!("'{}' must be a non-negative integer", key))?;
+            if limit == "min_choices" {
+                group.min = Some(value);
+            } else {
+                group.max = Some(value);
+            }
+        }
+
+        for group in &groups {
+            if let (Some(min), Some(max)) = (group.min, group.max) {
+                if min > max {
+                    return Err(format!(
+                        "group '{}' minimum ({}) must not exceed maximum ({})",
+                        group.name, min, max
+                    ));
+                }
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Why `count` selections from this group are not allowed, if they aren't
+    pub fn limit_error(&self, count: usize) -> Option<String> {
+        match (self.min, self.max) {
+            (Some(min), _) if count < min => Some(format!(
+                "Group '{}': at least {} choice(s) required",
+                self.name, min
+            )),
+            (_, Some(max)) if count > max => Some(format!(
+                "Group '{}': at most {} choice(s) allowed",
+                self.name, max
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of "did you mean" candidates reported for a typo
+const MAX_SUGGESTIONS: usize = 3;
+
 impl ChoiceValidator {
     pub fn new(choices: Vec<String>) -> Self {
         Self {
@@ -23,6 +116,10 @@ impl ChoiceValidator {
             priority: Priority::High,
             custom_message: None,
             selection_separator: ",".to_string(),
+            suggestions: true,
+            groups: Vec::new(),
+            disabled: Vec::new(),
+            locked: Vec::new(),
         }
     }
 
@@ -56,6 +153,32 @@ impl ChoiceValidator {
         self
     }
 
+    pub fn with_suggestions(mut self, suggestions: bool) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    pub fn with_groups(mut self, groups: Vec<ChoiceGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Reject `value` even though it is listed, explaining why (the reason may be empty)
+    pub fn with_disabled_choice(
+        mut self,
+        value: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        self.disabled.push((value.into(), reason.into()));
+        self
+    }
+
+    /// Require `value` to be among the selections
+    pub fn with_locked_choice(mut self, value: impl Into<String>) -> Self {
+        self.locked.push(value.into());
+        self
+    }
+
     /// Parse input for multiple choices using the configured separator
     fn parse_input(&self, input: &str) -> Vec<String> {
         if self.max_choices == 1 {
@@ -81,6 +204,51 @@ impl ChoiceValidator {
         }
     }
 
+    /// Find the choices closest to a rejected token, best match first. A choice
+    /// is a candidate when it is within a small edit distance of the token or
+    /// contains the token's characters in order (e.g. "prd" for "prod").
+    fn suggest(&self, choice: &str) -> Vec<String> {
+        let normalize = |s: &str| {
+            if self.case_sensitive {
+                s.to_string()
+            } else {
+                s.to_lowercase()
+            }
+        };
+        let typed: Vec<char> = normalize(choice).chars().collect();
+        if typed.is_empty() {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<(usize, &String)> = self
+            .choices
+            .iter()
+            .filter(|candidate| !self.disabled.iter().any(|(value, _)| value == *candidate))
+            .filter_map(|candidate| {
+                let option: Vec<char> = normalize(candidate).chars().collect();
+                let distance = edit_distance(&typed, &option);
+                // A token must keep some of its characters to be a typo, so
+                // a single character is never "close" to another
+                let threshold = (typed.len().max(option.len()) / 3)
+                    .max(1)
+                    .min(typed.len() - 1);
+                if distance <= threshold || (typed.len() > 1 && is_subsequence(&typed, &option)) {
+                    Some((distance, candidate))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Stable sort keeps the configured order among equally close choices
+        candidates.sort_by_key(|(distance, _)| *distance);
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| candidate.clone())
+            .collect()
+    }
+
     /// Get the canonical form of a choice (with correct case)
     fn get_canonical_choice(&self, choice: &str) -> Option<String> {
         if self.case_sensitive {
@@ -152,8 +320,27 @@ impl Validator for ChoiceValidator {
         }
 
         if !invalid_choices.is_empty() {
+            let suggestions: Vec<Vec<String>> = if self.suggestions {
+                invalid_choices.iter().map(|c| self.suggest(c)).collect()
+            } else {
+                Vec::new()
+            };
+
             let message = if let Some(msg) = &self.custom_message {
                 msg.clone()
+            } else if !suggestions.is_empty() && suggestions.iter().all(|s| !s.is_empty()) {
+                invalid_choices
+                    .iter()
+                    .zip(&suggestions)
+                    .map(|(choice, candidates)| {
+                        format!(
+                            "Unknown choice '{}'; did you mean {}?",
+                            choice,
+                            quoted_alternatives(candidates)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
             } else {
                 let valid_choices_str = self.choices.join(", ");
                 format!(
@@ -162,7 +349,83 @@ impl Validator for ChoiceValidator {
                     valid_choices_str
                 )
             };
-            return ValidationResult::failure("choice", self.priority, &message);
+
+            let mut result = ValidationResult::failure("choice", self.priority, &message)
+                .with_metadata("invalid_choices", serde_json::json!(invalid_choices));
+            if self.suggestions {
+                let suggestions: serde_json::Map<String, serde_json::Value> = invalid_choices
+                    .iter()
+                    .cloned()
+                    .zip(suggestions.into_iter().map(serde_json::Value::from))
+                    .collect();
+                result =
+                    result.with_metadata("suggestions", serde_json::Value::Object(suggestions));
+            }
+            return result;
+        }
+
+        let selected: Vec<String> = parsed_choices
+            .iter()
+            .filter_map(|choice| self.get_canonical_choice(choice))
+            .collect();
+
+        // Listed but unavailable choices
+        let disabled: Vec<&(String, String)> = self
+            .disabled
+            .iter()
+            .filter(|(value, _)| selected.contains(value))
+            .collect();
+        if !disabled.is_empty() {
+            let message = self.custom_message.clone().unwrap_or_else(|| {
+                disabled
+                    .iter()
+                    .map(|(value, reason)| {
+                        if reason.is_empty() {
+                            format!("Choice '{}' is unavailable.", value)
+                        } else {
+                            format!("Choice '{}' is unavailable: {}.", value, reason)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+            let values: Vec<&String> = disabled.iter().map(|(value, _)| value).collect();
+            return ValidationResult::failure("choice", self.priority, &message)
+                .with_metadata("disabled_choices", serde_json::json!(values));
+        }
+
+        // Locked choices are always part of the answer
+        let missing: Vec<&String> = self
+            .locked
+            .iter()
+            .filter(|value| !selected.contains(value))
+            .collect();
+        if !missing.is_empty() {
+            let message = self.custom_message.clone().unwrap_or_else(|| {
+                format!(
+                    "Locked choice(s) must be selected: {}",
+                    missing
+                        .iter()
+                        .map(|value| value.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            });
+            return ValidationResult::failure("choice", self.priority, &message)
+                .with_metadata("missing_locked_choices", serde_json::json!(missing));
+        }
+
+        // Check per-group limits against the canonical selections
+        for group in &self.groups {
+            let count = selected
+                .iter()
+                .filter(|choice| group.members.contains(choice))
+                .count();
+            if let Some(error) = group.limit_error(count) {
+                let message = self.custom_message.clone().unwrap_or(error);
+                return ValidationResult::failure("choice", self.priority, &message)
+                    .with_metadata("group", serde_json::json!(group.name));
+            }
         }
 
         ValidationResult::success("choice")
@@ -256,3 +519,205 @@ impl Validator for ChoiceValidator {
         "choice"
     }
 }
+
+/// Optimal string alignment distance: Levenshtein distance that also counts
+/// swapping two adjacent characters ("prdo" -> "prod") as a single edit
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Whether all characters of `needle` appear in `haystack` in order
+fn is_subsequence(needle: &[char], haystack: &[char]) -> bool {
+    let mut remaining = haystack.iter();
+    needle.iter().all(|c| remaining.any(|h| h == c))
+}
+
+/// Format candidates as "'a'", "'a' or 'b'" or "'a', 'b' or 'c'"
+fn quoted_alternatives(candidates: &[String]) -> String {
+    let quoted: Vec<String> = candidates.iter().map(|c| format!("'{}'", c)).collect();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => quoted.join(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environments() -> ChoiceValidator {
+        ChoiceValidator::new(vec![
+            "dev".to_string(),
+            "staging".to_string(),
+            "prod".to_string(),
+        ])
+    }
+
+    #[test]
+    fn test_choice_validator_suggests_closest_match() {
+        let result = environments().validate("prdo");
+        assert!(!result.passed);
+        assert_eq!(
+            result.message.unwrap(),
+            "Unknown choice 'prdo'; did you mean 'prod'?"
+        );
+        assert_eq!(
+            result.metadata.get("suggestions").unwrap(),
+            &serde_json::json!({ "prdo": ["prod"] })
+        );
+
+        let result = environments().validate("stg");
+        assert_eq!(
+            result.message.unwrap(),
+            "Unknown choice 'stg'; did you mean 'staging'?"
+        );
+    }
+
+    #[test]
+    fn test_choice_validator_without_close_match() {
+        let result = environments().validate("kubernetes");
+        assert!(!result.passed);
+        assert_eq!(
+            result.message.unwrap(),
+            "Invalid choice(s): kubernetes. Valid options: dev, staging, prod"
+        );
+        assert_eq!(
+            result.metadata.get("suggestions").unwrap(),
+            &serde_json::json!({ "kubernetes": [] })
+        );
+    }
+
+    #[test]
+    fn test_choice_validator_no_suggestions_for_one_character() {
+        let validator = ChoiceValidator::new(vec!["x".to_string(), "y".to_string()]);
+        let result = validator.validate("a");
+        assert_eq!(
+            result.message.unwrap(),
+            "Invalid choice(s): a. Valid options: x, y"
+        );
+
+        // Two characters may still be a typo of a two-character choice
+        let validator = ChoiceValidator::new(vec!["eu".to_string(), "us".to_string()]);
+        assert_eq!(
+            validator.validate("ux").message.unwrap(),
+            "Unknown choice 'ux'; did you mean 'us'?"
+        );
+    }
+
+    #[test]
+    fn test_choice_validator_suggestions_for_each_invalid_choice() {
+        let validator = ChoiceValidator::new(vec![
+            "auth".to_string(),
+            "cache".to_string(),
+            "logs".to_string(),
+        ])
+        .max_choices(3);
+
+        let result = validator.validate("auht,cahce,logs");
+        assert_eq!(
+            result.message.unwrap(),
+            "Unknown choice 'auht'; did you mean 'auth'? Unknown choice 'cahce'; did you mean 'cache'?"
+        );
+        assert_eq!(
+            result.metadata.get("invalid_choices").unwrap(),
+            &serde_json::json!(["auht", "cahce"])
+        );
+    }
+
+    #[test]
+    fn test_choice_validator_suggestions_disabled() {
+        let result = environments().with_suggestions(false).validate("prdo");
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Invalid choice(s): prdo"));
+        assert!(!result.metadata.contains_key("suggestions"));
+    }
+
+    #[test]
+    fn test_choice_validator_group_limits() {
+        let choices = vec![
+            ChoiceOption::new("api").with_group("Backend"),
+            ChoiceOption::new("db").with_group("Backend"),
+            ChoiceOption::new("web").with_group("Frontend"),
+        ];
+        let parameters = HashMap::from([
+            ("min_choices:Frontend".to_string(), "1".to_string()),
+            ("max_choices:Backend".to_string(), "1".to_string()),
+        ]);
+        let groups = ChoiceGroup::from_choices(&choices, &parameters).unwrap();
+        assert_eq!(groups[0].members, vec!["api", "db"]);
+        assert_eq!((groups[0].min, groups[0].max), (None, Some(1)));
+
+        let validator = ChoiceValidator::new(vec!["api".into(), "db".into(), "web".into()])
+            .max_choices(3)
+            .with_groups(groups);
+        assert!(validator.validate("API,web").passed);
+        assert_eq!(
+            validator.validate("api,db,web").message.unwrap(),
+            "Group 'Backend': at most 1 choice(s) allowed"
+        );
+        assert_eq!(
+            validator.validate("db").message.unwrap(),
+            "Group 'Frontend': at least 1 choice(s) required"
+        );
+
+        let unknown = HashMap::from([("max_choices:Mobile".to_string(), "1".to_string())]);
+        assert!(ChoiceGroup::from_choices(&choices, &unknown)
+            .unwrap_err()
+            .contains("unknown choice group 'Mobile'"));
+    }
+
+    #[test]
+    fn test_choice_validator_disabled_and_locked() {
+        let validator = environments()
+            .max_choices(3)
+            .with_disabled_choice("staging", "deploy freeze until Friday")
+            .with_locked_choice("dev");
+
+        assert!(validator.validate("DEV,prod").passed);
+        assert_eq!(
+            validator.validate("dev,staging").message.unwrap(),
+            "Choice 'staging' is unavailable: deploy freeze until Friday."
+        );
+        assert_eq!(
+            validator.validate("prod").message.unwrap(),
+            "Locked choice(s) must be selected: dev"
+        );
+
+        // Disabled choices are never suggested
+        let result = validator.validate("stagin");
+        assert!(result.message.unwrap().starts_with("Invalid choice(s)"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("prod"), &chars("prod")), 0);
+        assert_eq!(edit_distance(&chars("prdo"), &chars("prod")), 1);
+        assert_eq!(edit_distance(&chars("dve"), &chars("dev")), 1);
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+    }
+}
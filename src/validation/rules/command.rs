@@ -0,0 +1,258 @@
+use super::super::{PartialValidationResult, Priority, ValidationResult, Validator};
+use crate::error::{PromptError, Result};
+use std::io::{Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Exit codes the shell uses when a command cannot be found or executed
+const SHELL_NOT_EXECUTABLE: i32 = 126;
+const SHELL_NOT_FOUND: i32 = 127;
+
+/// Validator that pipes input to an external command and passes on exit status 0
+#[derive(Debug)]
+pub struct ExternalCommandValidator {
+    command: String,
+    timeout: Duration,
+    priority: Priority,
+    custom_message: Option<String>,
+}
+
+impl ExternalCommandValidator {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            timeout: Duration::from_secs(10),
+            priority: Priority::High,
+            custom_message: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.custom_message = Some(message.into());
+        self
+    }
+
+    fn spawn(&self) -> Result<Child> {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(&self.command);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.command);
+            command
+        };
+
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| PromptError::CommandSpawn(format!("{}: {}", self.command, e)))
+    }
+
+    /// Wait for the child to exit, killing it once the timeout elapses
+    fn wait_with_timeout(&self, child: &mut Child) -> Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn failure_message(&self, stderr_output: &str) -> String {
+        if let Some(msg) = &self.custom_message {
+            msg.clone()
+        } else if !stderr_output.trim().is_empty() {
+            stderr_output.trim().to_string()
+        } else {
+            format!("Rejected by validation command: {}", self.command)
+        }
+    }
+}
+
+impl Validator for ExternalCommandValidator {
+    fn validate(&self, input: &str) -> ValidationResult {
+        match self.try_validate(input) {
+            Ok(result) => result,
+            Err(e) => ValidationResult::failure("command", self.priority, e.to_string()),
+        }
+    }
+
+    fn try_validate(&self, input: &str) -> Result<ValidationResult> {
+        let mut child = self.spawn()?;
+
+        // Feed stdin and drain stderr on separate threads so a command that
+        // ignores its input or writes a lot of output cannot block us
+        let stdin_writer = child.stdin.take().map(|mut stdin| {
+            let input = input.to_string();
+            thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            })
+        });
+        let stderr_reader = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut output = String::new();
+                let _ = stderr.read_to_string(&mut output);
+                output
+            })
+        });
+
+        // On timeout the I/O threads are left detached: processes spawned by
+        // the shell may still hold the pipes open after it has been killed
+        let Some(status) = self.wait_with_timeout(&mut child)? else {
+            return Ok(ValidationResult::failure(
+                "command",
+                self.priority,
+                format!(
+                    "Validation command timed out after {}s",
+                    self.timeout.as_secs_f64()
+                ),
+            ));
+        };
+
+        if let Some(writer) = stdin_writer {
+            let _ = writer.join();
+        }
+        let stderr_output = stderr_reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+
+        match status.code() {
+            Some(0) => Ok(ValidationResult::success("command")),
+            Some(code @ (SHELL_NOT_EXECUTABLE | SHELL_NOT_FOUND)) if !cfg!(windows) => {
+                Err(PromptError::CommandSpawn(format!(
+                    "{}: exited with status {}{}",
+                    self.command,
+                    code,
+                    if stderr_output.trim().is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({})", stderr_output.trim())
+                    }
+                )))
+            }
+            code => {
+                let mut result = ValidationResult::failure(
+                    "command",
+                    self.priority,
+                    self.failure_message(&stderr_output),
+                )
+                .with_metadata("command", serde_json::Value::String(self.command.clone()));
+                if let Some(code) = code {
+                    result =
+                        result.with_metadata("exit_code", serde_json::Value::Number(code.into()));
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn partial_validate(&self, _input: &str, _cursor_pos: usize) -> PartialValidationResult {
+        // External commands are only run on submit
+        PartialValidationResult::valid()
+    }
+
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn description(&self) -> &str {
+        &self.command
+    }
+
+    fn submit_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_validator_passes_on_zero_exit() {
+        let validator = ExternalCommandValidator::new("grep -q '^alice$'");
+
+        let result = validator.validate("alice");
+        assert!(result.passed);
+        assert_eq!(result.rule_name, "command");
+    }
+
+    #[test]
+    fn test_command_validator_uses_stderr_as_message() {
+        let validator = ExternalCommandValidator::new("echo 'Username taken' >&2; exit 1");
+
+        let result = validator.validate("bob");
+        assert!(!result.passed);
+        assert_eq!(result.message.unwrap(), "Username taken");
+        assert_eq!(
+            result.metadata.get("exit_code").unwrap(),
+            &serde_json::Value::Number(1.into())
+        );
+    }
+
+    #[test]
+    fn test_command_validator_custom_message() {
+        let validator = ExternalCommandValidator::new("echo 'ignored' >&2; exit 1")
+            .with_message("Username not available");
+
+        let result = validator.validate("bob");
+        assert!(!result.passed);
+        assert_eq!(result.message.unwrap(), "Username not available");
+    }
+
+    #[test]
+    fn test_command_validator_timeout() {
+        let validator =
+            ExternalCommandValidator::new("sleep 5").with_timeout(Duration::from_millis(100));
+
+        let result = validator.validate("anything");
+        assert!(!result.passed);
+        assert!(result.message.unwrap().contains("timed out"));
+    }
+
+    #[test]
+    fn test_command_validator_missing_command_is_spawn_error() {
+        let validator = ExternalCommandValidator::new("askr-no-such-command-for-tests");
+
+        let result = validator.try_validate("anything");
+        assert!(matches!(result, Err(PromptError::CommandSpawn(_))));
+    }
+
+    #[test]
+    fn test_command_validator_is_submit_only() {
+        let validator = ExternalCommandValidator::new("false");
+
+        assert!(validator.submit_only());
+        assert!(validator
+            .partial_validate("anything", 3)
+            .first_error_pos
+            .is_none());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod basic;
 pub mod choice;
+pub mod command;
 pub mod datetime;
 pub mod filesystem;
 pub mod format;
@@ -7,6 +8,7 @@ pub mod numeric;
 
 pub use basic::*;
 pub use choice::*;
+pub use command::*;
 pub use datetime::*;
 pub use filesystem::*;
 pub use format::*;
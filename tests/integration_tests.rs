@@ -192,6 +192,142 @@ fn test_choice_validation() {
             || stderr.contains("Valid options")
             || stderr.contains("Validation failed")
     );
+
+    // Test typo suggestions in quiet mode
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &["--quiet", "--choices", "dev,staging,prod", "Environment:"],
+        "prdo",
+    );
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Unknown choice 'prdo'; did you mean 'prod'?"));
+
+    // Test labelled choices validate and output the underlying value
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "--choices",
+            "dev=Development,prod=Production",
+            "Environment:",
+        ],
+        "prod",
+    );
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "prod");
+
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "--choices",
+            r#"[{"value": "prod", "label": "Production", "description": "us-east-1"}]"#,
+            "Environment:",
+        ],
+        "prod",
+    );
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "prod");
+
+    // Test per-group limits on grouped choices
+    let grouped = [
+        "--quiet",
+        "--choices",
+        "Backend:api,db;Frontend:web,mobile",
+        "--group-max-choices",
+        "Backend=1",
+        "Components:",
+    ];
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(&grouped, "api,web");
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "api,web");
+
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(&grouped, "api,db");
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Group 'Backend': at most 1 choice(s) allowed"));
+
+    let (exit_code, _stdout, stderr) = run_prompt(&[
+        "--choices",
+        "Backend:api,db",
+        "--group-min-choices",
+        "Mobile=1",
+        "Components:",
+    ]);
+
+    assert_eq!(exit_code, 2);
+    assert!(stderr.contains("unknown choice group 'Mobile'"));
+}
+
+#[test]
+fn test_disabled_and_locked_choices() {
+    let args = [
+        "--quiet",
+        "--choices",
+        "dev,staging,prod,core",
+        "--disabled-choice",
+        "staging=deploy freeze until Friday",
+        "--locked-choice",
+        "core",
+        "Deploy to:",
+    ];
+
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(&args, "prod,core");
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "prod,core");
+
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(&args, "staging,core");
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Choice 'staging' is unavailable: deploy freeze until Friday."));
+
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(&args, "prod");
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Locked choice(s) must be selected: core"));
+
+    let (exit_code, _stdout, stderr) =
+        run_prompt(&["--choices", "dev,prod", "--disabled-choice", "qa", "Env:"]);
+
+    assert_eq!(exit_code, 2);
+    assert!(stderr.contains("--disabled-choice refers to unknown choice 'qa'"));
+}
+
+#[test]
+fn test_choices_from_file_and_command() {
+    // Test choices read from a file, one per line
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--quiet", "--choices-file", "examples/regions.txt"],
+        "eu-west-1",
+    );
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "eu-west-1");
+
+    let (exit_code, _stdout, stderr) =
+        run_prompt(&["--quiet", "--choices-file", "no-such-choices.txt"]);
+
+    assert_eq!(exit_code, 2);
+    assert!(stderr.contains("Could not load choices from no-such-choices.txt"));
+
+    // Test choices taken from a command's output
+    #[cfg(unix)]
+    {
+        let (exit_code, stdout, _stderr) = run_prompt_with_input(
+            &["--quiet", "--choices-cmd", "printf 'main\\ndevelop\\n'"],
+            "develop",
+        );
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(stdout.trim(), "develop");
+
+        let (exit_code, _stdout, stderr) = run_prompt(&["--quiet", "--choices-cmd", "exit 3"]);
+
+        assert_eq!(exit_code, 2);
+        assert!(stderr.contains("exited with status 3"));
+    }
 }
 
 #[test]
@@ -228,6 +364,162 @@ fn test_file_validation() {
     assert!(stderr.contains("exist") || stderr.contains("Validation failed"));
 }
 
+#[cfg(unix)]
+#[test]
+fn test_command_validation() {
+    // Test command that accepts the input
+    let (exit_code, stdout, _stderr) =
+        run_prompt_with_input(&["--quiet", "--validate-cmd", "grep -q '^alice$'"], "alice");
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "alice");
+
+    // Test command that rejects the input with a custom message
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "--validate-cmd",
+            "grep -q '^alice$'",
+            "--validate-cmd-message",
+            "Username not available",
+        ],
+        "bob",
+    );
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Username not available"));
+
+    // Test command that cannot be run
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "--validate-cmd",
+            "askr-no-such-command-for-tests",
+        ],
+        "alice",
+    );
+
+    assert_ne!(exit_code, 0);
+    assert!(stderr.contains("Validation command could not be run"));
+}
+
+#[test]
+fn test_rules_file() {
+    // Test rules loaded from a shared file
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--quiet", "--rules", "examples/username.toml"],
+        "alice_01",
+    );
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "alice_01");
+
+    let (exit_code, _stdout, stderr) =
+        run_prompt_with_input(&["--quiet", "--rules", "examples/username.toml"], "1alice");
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Must start with a letter"));
+
+    // Test CLI flags overriding a rule of the same type from the file
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "--rules",
+            "examples/username.toml",
+            "--max-length",
+            "5",
+        ],
+        "alice_01",
+    );
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("Maximum length is 5"));
+
+    // Test missing rules file
+    let (exit_code, _stdout, stderr) = run_prompt(&["--quiet", "--rules", "no-such-rules.toml"]);
+
+    assert_eq!(exit_code, 2);
+    assert!(stderr.contains("Invalid rules file"));
+}
+
+#[test]
+fn test_form_quiet_mode() {
+    // Test answering every field of a form spec from stdin
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--quiet", "form", "examples/setup_form.toml"],
+        "demo\nprod\n\nsecret123\n",
+    );
+
+    assert_eq!(exit_code, 0);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON output");
+    assert_eq!(json["project"], "demo");
+    assert_eq!(json["environment"], "prod");
+    assert_eq!(json["port"], "8080");
+    assert_eq!(json["api_token"], "secret123");
+
+    // Test shell-sourceable output
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &[
+            "--quiet",
+            "form",
+            "examples/setup_form.toml",
+            "--output",
+            "env",
+        ],
+        "demo\n\n9000\nit's-secret\n",
+    );
+
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("PROJECT='demo'"));
+    assert!(stdout.contains("ENVIRONMENT='dev'"));
+    assert!(stdout.contains("PORT='9000'"));
+    assert!(stdout.contains("API_TOKEN='it'\\''s-secret'"));
+
+    // Test a field failing validation
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &["--quiet", "form", "examples/setup_form.toml"],
+        "demo\nqa\n\nsecret123\n",
+    );
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("environment:"));
+}
+
+#[test]
+fn test_form_conditional_fields() {
+    // Test skipping fields whose condition is not met
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--quiet", "form", "examples/deploy_form.toml"],
+        "http\n\n\n",
+    );
+
+    assert_eq!(exit_code, 0);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON output");
+    assert_eq!(json["protocol"], "http");
+    assert!(json.get("tls_cert").is_none());
+    assert_eq!(json["port"], "8443");
+
+    // Test asking them when it is
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--quiet", "form", "examples/deploy_form.toml"],
+        "https\nCargo.toml\nCargo.lock\n\n\n",
+    );
+
+    assert_eq!(exit_code, 0);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON output");
+    assert_eq!(json["tls_cert"], "Cargo.toml");
+    assert_eq!(json["tls_key"], "Cargo.lock");
+
+    // Test a rule referring to an earlier answer
+    let (exit_code, _stdout, stderr) = run_prompt_with_input(
+        &["--quiet", "form", "examples/deploy_form.toml"],
+        "http\n8000\n8443\n",
+    );
+
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("port: Must be between 1 and 8000"));
+}
+
 #[test]
 fn test_json_output() {
     let (exit_code, stdout, _stderr) =
@@ -252,6 +544,26 @@ fn test_quiet_mode() {
     assert_eq!(stdout.trim(), "hello");
 }
 
+#[test]
+fn test_multiline_input() {
+    // Without a terminal, multi-line text runs to the end of input
+    let notes = "Release 1.2\n\n- faster startup\n- fewer crashes\n";
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(
+        &["--multiline", "--pattern", "(?m)^- ", "Release notes:"],
+        notes,
+    );
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim_end(), notes.trim_end());
+
+    let (exit_code, stdout, _stderr) = run_prompt_with_input(&["Release notes:"], notes);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.trim(), "Release 1.2");
+
+    let (exit_code, _stdout, stderr) = run_prompt(&["--multiline", "--mask", "Key:"]);
+    assert_ne!(exit_code, 0);
+    assert!(stderr.contains("cannot be used with"));
+}
+
 #[test]
 fn test_multiple_validators() {
     // Test all validators pass